@@ -0,0 +1,8 @@
+-- ルームごとのテーブル設定（ブラインド・アンティ・初期スタック・席数）
+ALTER TABLE rooms
+    ADD COLUMN small_blind INTEGER NOT NULL DEFAULT 10,
+    ADD COLUMN big_blind INTEGER NOT NULL DEFAULT 20,
+    ADD COLUMN ante INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN starting_stack INTEGER NOT NULL DEFAULT 1000,
+    ADD COLUMN max_seats INTEGER NOT NULL DEFAULT 9,
+    ADD COLUMN min_players INTEGER NOT NULL DEFAULT 2;
@@ -0,0 +1,2 @@
+-- トーナメントモードの設定（ブラインドレベル表）。NULLならキャッシュゲーム
+ALTER TABLE rooms ADD COLUMN tournament JSONB;
@@ -0,0 +1,4 @@
+-- 1手あたりの持ち時間とタイムバンク（秒）
+ALTER TABLE rooms
+    ADD COLUMN action_timeout_secs INTEGER NOT NULL DEFAULT 30,
+    ADD COLUMN time_bank_secs INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,20 @@
+-- 終了したハンドごとの結果（戦績）
+CREATE TABLE game_results (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    room_id UUID NOT NULL REFERENCES rooms(id),
+    pot INTEGER NOT NULL,
+    winning_hand_rank VARCHAR(50), -- 全員フォールドで終わった場合はNULL
+    finished_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- ハンドの参加者ごとの結果
+CREATE TABLE game_result_players (
+    game_result_id UUID NOT NULL REFERENCES game_results(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id),
+    net_chips INTEGER NOT NULL, -- ハンド開始時からのチップの増減
+    hand_rank VARCHAR(50),      -- ショーダウンまで残った場合の役
+    is_winner BOOLEAN NOT NULL,
+    PRIMARY KEY (game_result_id, user_id)
+);
+
+CREATE INDEX idx_game_result_players_user_id ON game_result_players (user_id);
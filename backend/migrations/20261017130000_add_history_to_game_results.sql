@@ -0,0 +1,2 @@
+-- ハンドのリプレイ用の記録（着席・ブラインド・手札・アクション・ボード・ショーダウン・ポット分配）
+ALTER TABLE game_results ADD COLUMN history JSONB;
@@ -0,0 +1,2 @@
+-- ハンド履歴のエクスポート用の通し番号（PokerStars形式では数値のハンド番号が必要）
+ALTER TABLE game_results ADD COLUMN hand_number BIGSERIAL;
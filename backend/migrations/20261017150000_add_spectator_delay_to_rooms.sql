@@ -0,0 +1,2 @@
+-- 観戦者への配信ディレイ（秒）
+ALTER TABLE rooms ADD COLUMN spectator_delay_secs INTEGER NOT NULL DEFAULT 0;
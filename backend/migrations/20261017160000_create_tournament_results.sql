@@ -0,0 +1,7 @@
+-- トーナメントの最終順位
+CREATE TABLE tournament_results (
+    room_id UUID NOT NULL REFERENCES rooms(id),
+    user_id UUID NOT NULL REFERENCES users(id),
+    place INTEGER NOT NULL, -- 1が優勝
+    PRIMARY KEY (room_id, user_id)
+);
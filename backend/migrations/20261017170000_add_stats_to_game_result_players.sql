@@ -0,0 +1,14 @@
+-- HUD統計用の、ハンドごとの集計値。統計はこれを合計して求める
+-- ハンドの保存時に書き込む（記録を残していないハンドは0のまま）
+ALTER TABLE game_result_players
+    ADD COLUMN hands INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN vpip_hands INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN pfr_hands INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN three_bet_opportunities INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN three_bets INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN postflop_aggressive INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN postflop_calls INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN saw_flop INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN went_to_showdown INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN won_at_showdown INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN total_bb DOUBLE PRECISION NOT NULL DEFAULT 0;
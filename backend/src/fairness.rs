@@ -0,0 +1,90 @@
+use crate::game::Deck;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+// クライアントが送れるシードの最大文字数
+pub const MAX_CLIENT_SEED_LEN: usize = 64;
+
+// シャッフルに混ぜたプレイヤーのシード
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClientSeed {
+    pub username: String,
+    pub seed: String,
+}
+
+// 1ハンド分のシャッフルの証明。
+// サーバーシードのハッシュは配る前に公開し、サーバーシード本体はハンドが終わってから誰にでも公開する。
+// 公開後はデッキ全体を作り直せるので、フォールド・マックした手札もハンドの後には分かる（ハンド中は隠す）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ShuffleProof {
+    pub server_seed_hash: String,      // 16進数のSHA-256
+    pub server_seed: Option<String>,   // 16進数。ハンド中はNone
+    pub client_seeds: Vec<ClientSeed>, // ユーザー名順
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    ServerSeedNotRevealed,
+    InvalidServerSeed,
+    HashMismatch,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::ServerSeedNotRevealed => {
+                write!(f, "サーバーシードがまだ公開されていません。")
+            }
+            VerifyError::InvalidServerSeed => {
+                write!(f, "サーバーシードは32バイトの16進数で指定してください。")
+            }
+            VerifyError::HashMismatch => {
+                write!(
+                    f,
+                    "サーバーシードが事前に公開されたハッシュと一致しません。"
+                )
+            }
+        }
+    }
+}
+
+// 配る前に公開するサーバーシードのハッシュ
+pub fn hash_server_seed(server_seed: &[u8; 32]) -> String {
+    hex::encode(Sha256::digest(server_seed))
+}
+
+// サーバーシードとプレイヤーのシードを混ぜて、デッキのシャッフルに使うシードを作る
+pub fn deck_seed(server_seed: &[u8; 32], client_seeds: &[ClientSeed]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(server_seed);
+    for client_seed in client_seeds {
+        // 区切りがずれて別の組み合わせと同じ入力にならないよう、長さを前に付ける
+        for part in [&client_seed.username, &client_seed.seed] {
+            hasher.update((part.len() as u32).to_be_bytes());
+            hasher.update(part.as_bytes());
+        }
+    }
+    hasher.finalize().into()
+}
+
+// ハンドで配るデッキを作る。ゲームと検証で同じ関数を使う
+pub fn create_deck(server_seed: &[u8; 32], client_seeds: &[ClientSeed]) -> Deck {
+    Deck::shuffled(deck_seed(server_seed, client_seeds))
+}
+
+// 公開されたシードからデッキを作り直す。サーバーシードが事前に公開したハッシュと一致しなければエラー
+pub fn verify(proof: &ShuffleProof) -> Result<Deck, VerifyError> {
+    let server_seed = proof
+        .server_seed
+        .as_deref()
+        .ok_or(VerifyError::ServerSeedNotRevealed)?;
+    let server_seed: [u8; 32] = hex::decode(server_seed)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(VerifyError::InvalidServerSeed)?;
+    if !hash_server_seed(&server_seed).eq_ignore_ascii_case(&proof.server_seed_hash) {
+        return Err(VerifyError::HashMismatch);
+    }
+    Ok(create_deck(&server_seed, &proof.client_seeds))
+}
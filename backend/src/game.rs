@@ -1,307 +1,1443 @@
-use crate::hand_evaluator::{self, HandRank};
+use crate::fairness::{self, ClientSeed, MAX_CLIENT_SEED_LEN, ShuffleProof};
+use crate::hand_evaluator::{self, Card, HandRank, Rank, Suit};
+use crate::hand_history::{HandEvent, HandHistory, Seat};
+use crate::stats::PlayerStats;
+use crate::tournament::{BlindLevel, TournamentConfig, TournamentState};
+use chrono::{DateTime, Duration, Utc};
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use serde::{Deserialize, Serialize};
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+// 切断したプレイヤーの席を確保しておく時間（秒）
+const RECONNECT_GRACE_SECS: i64 = 120;
+
+// 全員オールインのとき、残りのストリートを1枚ずつ配る間隔（秒）
+const RUNOUT_DELAY_SECS: i64 = 2;
+
+// 1テーブルの最大人数（2枚×人数＋ボード5枚がデッキに収まる範囲）
+pub const MAX_SEATS_LIMIT: usize = 10;
+
+// 観戦ディレイの上限（秒）
+const MAX_SPECTATOR_DELAY_SECS: u32 = 600;
+
+// テーブルのルール設定（ルーム作成時に指定する）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TableConfig {
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub ante: u32,
+    pub starting_stack: u32,
+    pub max_seats: usize,
+    pub min_players: usize,        // ゲーム開始に必要な人数
+    pub action_timeout_secs: u32,  // 1手あたりの持ち時間（0なら無制限）
+    pub time_bank_secs: u32,       // 持ち時間切れ後に使える各プレイヤーの予備時間
+    pub spectator_delay_secs: u32, // 観戦者への配信を遅らせる秒数（ゴースティング対策）
+    // 指定するとトーナメントモード（ブラインドはレベル表に従う）
+    pub tournament: Option<TournamentConfig>,
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        TableConfig {
+            small_blind: 10,
+            big_blind: 20,
+            ante: 0,
+            starting_stack: 1000,
+            max_seats: 9,
+            min_players: 2,
+            action_timeout_secs: 30,
+            time_bank_secs: 0,
+            spectator_delay_secs: 0,
+            tournament: None,
+        }
+    }
+}
+
+impl TableConfig {
+    // 設定値が矛盾していないかチェックする
+    pub fn validate(&self) -> Result<(), String> {
+        if self.big_blind == 0 || self.small_blind > self.big_blind {
+            return Err("Small blind must not exceed a non-zero big blind".to_string());
+        }
+        if self.starting_stack == 0 {
+            return Err("Starting stack must be positive".to_string());
+        }
+        // DBにはINTEGERで保存するため
+        if [
+            self.big_blind,
+            self.ante,
+            self.starting_stack,
+            self.action_timeout_secs,
+            self.time_bank_secs,
+        ]
+        .iter()
+        .any(|&amount| amount > i32::MAX as u32)
+        {
+            return Err("Chip amounts are too large".to_string());
+        }
+        if !(2..=MAX_SEATS_LIMIT).contains(&self.max_seats) {
+            return Err(format!(
+                "Max seats must be between 2 and {}",
+                MAX_SEATS_LIMIT
+            ));
+        }
+        // テーブル上のチップの合計（ポットや記録される収支の上限）もINTEGERに収める
+        if self.starting_stack as u64 * self.max_seats as u64 > i32::MAX as u64 {
+            return Err("Starting stack is too large for the number of seats".to_string());
+        }
+        if self.min_players < 2 || self.min_players > self.max_seats {
+            return Err("Min players must be between 2 and max seats".to_string());
+        }
+        if self.spectator_delay_secs > MAX_SPECTATOR_DELAY_SECS {
+            return Err(format!(
+                "Spectator delay must be at most {} seconds",
+                MAX_SPECTATOR_DELAY_SECS
+            ));
+        }
+        if let Some(tournament) = &self.tournament {
+            tournament.validate()?;
+        }
+        Ok(())
+    }
+}
 
 // サーバーとクライアント間でやり取りされるメッセージの定義
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "type", content = "payload")]
 pub enum GameMessage {
     PlayerAction(PlayerAction),
-    GameStateUpdate(GameState),
+    GameStateUpdate(Box<GameState>), // 他のバリアントに比べて大きいのでBoxで持つ
     DealHand(DealHandPayload),
+    LegalActions(LegalActions),
     ChatMessage(String),
+    ClientSeed(String), // 次のハンドからシャッフルに混ぜるプレイヤーのシード
+    Error(ErrorPayload),
+    PlayerStats(Vec<PlayerStats>), // 同じテーブルのプレイヤーのHUD統計
 }
 
 // クライアントから送られてくるアクション
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 #[serde(tag = "action")]
 pub enum PlayerAction {
     StartGame,
+    TakeSeat { seat: usize },
+    SitOut,
+    SitIn,
+    LeaveTable,
     Fold,
+    Check,
     Call,
-    Bet { amount: u32 },
+    Bet { amount: u32 }, // まだ誰もベットしていないときのベット（このラウンドの合計額）
+    Raise { to: u32 },   // 現在のベットへのレイズ（このラウンドの合計額）
+    AllIn,
     NextHand,
+    ShowCards, // ハンド終了後に自分から手札を見せる
 }
 
 // サーバーから特定のプレイヤーに手札を送るためのペイロード
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DealHandPayload {
-    pub cards: Vec<String>,
+    pub cards: Vec<Card>,
+}
+
+// 無効な操作をしたクライアントにだけ返すエラー
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ErrorPayload {
+    pub error: GameError,
+    pub message: String,
+}
+
+impl From<GameError> for ErrorPayload {
+    fn from(error: GameError) -> Self {
+        ErrorPayload {
+            message: error.to_string(),
+            error,
+        }
+    }
+}
+
+// ゲーム操作が受け付けられなかった理由
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "code")]
+pub enum GameError {
+    InvalidMessage,
+    GameAlreadyStarted,
+    NotEnoughPlayers { required: usize },
+    TableFull,
+    TournamentInProgress,
+    TournamentFinished,
+    Spectating,
+    InvalidSeat,
+    SeatTaken,
+    AlreadySeated,
+    PlayerNotFound,
+    NotYourTurn,
+    HandNotFinished,
+    CannotCheck,
+    NothingToCall,
+    InvalidBet { min: u32, max: u32 },
+    InvalidRaise { min: u32, max: u32 },
+    CannotBet,
+    CannotRaise,
+    NoCardsToShow,
+    InvalidClientSeed,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::InvalidMessage => write!(f, "メッセージの形式が正しくありません。"),
+            GameError::GameAlreadyStarted => write!(f, "ゲームはすでに開始されています。"),
+            GameError::NotEnoughPlayers { required } => {
+                write!(
+                    f,
+                    "ゲームを開始するには{}人以上のプレイヤーが必要です。",
+                    required
+                )
+            }
+            GameError::TableFull => write!(f, "テーブルが満席です。"),
+            GameError::TournamentInProgress => {
+                write!(f, "トーナメントはすでに開始されているため参加できません。")
+            }
+            GameError::TournamentFinished => write!(f, "トーナメントは終了しました。"),
+            GameError::Spectating => write!(f, "観戦中はゲームを操作できません。"),
+            GameError::InvalidSeat => write!(f, "その席番号はありません。"),
+            GameError::SeatTaken => write!(f, "その席にはすでに他のプレイヤーが座っています。"),
+            GameError::AlreadySeated => write!(f, "すでに着席しています。"),
+            GameError::PlayerNotFound => write!(f, "このゲームに参加していません。"),
+            GameError::NotYourTurn => write!(f, "あなたの手番ではありません。"),
+            GameError::HandNotFinished => write!(f, "ハンドがまだ終了していません。"),
+            GameError::CannotCheck => write!(f, "コールが必要なためチェックできません。"),
+            GameError::NothingToCall => write!(f, "コールする額がありません。"),
+            GameError::InvalidBet { min, max } => {
+                write!(f, "ベット額は{}から{}の間で指定してください。", min, max)
+            }
+            GameError::InvalidRaise { min, max } => {
+                write!(f, "レイズ額は{}から{}の間で指定してください。", min, max)
+            }
+            GameError::CannotBet => write!(f, "現在はベットできません。"),
+            GameError::CannotRaise => write!(f, "現在はレイズできません。"),
+            GameError::NoCardsToShow => write!(f, "見せられる手札がありません。"),
+            GameError::InvalidClientSeed => write!(
+                f,
+                "シードは1文字以上{}文字以下で指定してください。",
+                MAX_CLIENT_SEED_LEN
+            ),
+        }
+    }
+}
+
+// ベット・レイズできる額の範囲（このラウンドの合計額）
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BetRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl BetRange {
+    fn contains(&self, amount: u32) -> bool {
+        self.min <= amount && amount <= self.max
+    }
+}
+
+// 手番のプレイヤーが現在取れるアクションの一覧
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LegalActions {
+    pub username: String,
+    pub can_fold: bool,
+    pub can_check: bool,
+    pub call_amount: Option<u32>, // コールに必要な額（スタックが足りなければ全額）
+    pub bet: Option<BetRange>,
+    pub raise: Option<BetRange>,
+    pub all_in: Option<u32>, // オールインした場合のこのラウンドの合計額
 }
 
 // プレイヤーの状態
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Player {
     pub username: String,
+    pub seat: usize, // 席番号（0始まり）。playersは席番号順に並べる
     pub stack: u32,
-    pub hand: Vec<String>,
+    pub hand: Vec<Card>,
     pub is_active: bool,
     pub current_bet: u32,
+    pub total_bet: u32, // このハンドで投入した合計額（サイドポット計算用）
+    pub is_all_in: bool,
+    pub has_acted: bool, // このラウンドで直近のフルレイズ以降にアクションしたか（ラウンド終了とレイズ権の判定用）
+    pub time_bank_secs: u32, // 残りのタイムバンク
+    pub disconnected_at: Option<DateTime<Utc>>, // 切断中なら切断した時刻
+    pub is_sitting_out: bool, // 着席したまま休憩中（キャッシュゲームでは配られない）
+    pub is_leaving: bool, // ハンドの途中で退席を申し出た（ハンド終了後に席を外す）
+    pub missed_small_blind: bool, // 休憩中にSBを払わずに済ませた（復帰時にデッドで払う）
+    pub missed_big_blind: bool, // 休憩中にBBを払わずに済ませた（復帰時にライブで払う）
+    pub shows_hand: bool, // 手札をテーブルに公開している（ショーダウン・オールイン・自分から見せた）
+}
+
+// 退席したプレイヤーが持ち帰ったチップ
+#[derive(Debug, Clone, PartialEq)]
+pub struct CashOut {
+    pub username: String,
+    pub stack: u32,
+}
+
+// 手番プレイヤーの持ち時間
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TurnTimer {
+    pub username: String,
+    pub deadline: DateTime<Utc>,
+    pub using_time_bank: bool,
+    #[serde(skip)]
+    time_bank_started_at: Option<DateTime<Utc>>,
+}
+
+// ポット（メインポット・サイドポット）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Pot {
+    pub amount: u32,
+    pub eligible_players: Vec<String>, // このポットを獲得する権利のあるプレイヤー
+}
+
+// 終了したハンドの参加者ごとの結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandResultPlayer {
+    pub username: String,
+    pub net_chips: i64,            // ハンド開始時からのチップの増減
+    pub hand_rank: Option<String>, // ショーダウンまで残った場合の役
+    pub is_winner: bool,
+}
+
+// 終了したハンドの結果（戦績としてDBに保存する）
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandResult {
+    pub pot: u32,
+    pub winning_hand_rank: Option<String>, // メインポットを獲得した役。全員フォールドならNone
+    pub players: Vec<HandResultPlayer>,
+    pub history: HandHistory,
+}
+
+// ゲームの進行フェーズ
+// シリアライズ後の文字列はクライアントとの互換性のため従来の表記を維持する
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamePhase {
+    Waiting,
+    #[serde(rename = "Pre-flop")]
+    PreFlop,
+    Flop,
+    Turn,
+    River,
+    Showdown,     // ショーダウンで勝者が決まった
+    HandComplete, // 全員がフォールドし、ショーダウンなしでハンドが終わった
+    Finished,     // トーナメントが終了した
+}
+
+impl GamePhase {
+    // ベッティングラウンドが終わったときに進むフェーズ
+    fn next_street(self) -> Option<GamePhase> {
+        match self {
+            GamePhase::PreFlop => Some(GamePhase::Flop),
+            GamePhase::Flop => Some(GamePhase::Turn),
+            GamePhase::Turn => Some(GamePhase::River),
+            GamePhase::River => Some(GamePhase::Showdown),
+            _ => None,
+        }
+    }
+
+    // ベッティングラウンドの途中か
+    pub fn is_betting(self) -> bool {
+        matches!(
+            self,
+            GamePhase::PreFlop | GamePhase::Flop | GamePhase::Turn | GamePhase::River
+        )
+    }
+
+    // ハンドが終わり、次のハンドを待っている状態か
+    pub fn is_hand_over(self) -> bool {
+        matches!(self, GamePhase::Showdown | GamePhase::HandComplete)
+    }
+
+    // 許可されている状態遷移
+    fn can_transition_to(self, next: GamePhase) -> bool {
+        match (self, next) {
+            (GamePhase::Waiting, GamePhase::PreFlop) => true,
+            (from, GamePhase::HandComplete) => from.is_betting(),
+            (from, to) if from.next_street() == Some(to) => true,
+            (from, GamePhase::Waiting | GamePhase::Finished) => from.is_hand_over(),
+            _ => false,
+        }
+    }
 }
 
 // ゲーム全体の現在の状態
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GameState {
     pub players: Vec<Player>,
-    pub community_cards: Vec<String>,
+    pub community_cards: Vec<Card>,
     pub pot: u32,
+    pub pots: Vec<Pot>,
     pub current_turn_username: Option<String>,
-    pub status: String, // e.g., "Waiting", "Pre-flop", "Flop", "Turn", "River", "Showdown"
+    status: GamePhase, // 遷移はtransition_toを通してのみ行う
     pub current_bet: u32,
-    pub dealer_index: usize,
+    pub min_raise: u32,             // 最低レイズ幅（直前のベット・レイズの大きさ）
+    pub button_seat: Option<usize>, // ボタンの席（空席のこともある）
+    pub small_blind_seat: Option<usize>, // SBの席（空席・休憩中ならSBは払われない）
+    pub big_blind_seat: Option<usize>,
+    pub last_aggressor: Option<String>, // このストリートで最後にベット・レイズしたプレイヤー
     pub winner_message: Option<String>,
+    pub config: TableConfig,
+    pub tournament: Option<TournamentState>,
+    pub turn_timer: Option<TurnTimer>,
+    pub runout_at: Option<DateTime<Utc>>, // 全員オールインのとき、次のストリートを配る時刻
+    pub seed_commitment: String,          // 次のハンドで使うサーバーシードのハッシュ
+    pub client_seeds: BTreeMap<String, String>, // 次のハンドから混ぜるプレイヤーのシード
+    pub shuffle_proof: Option<ShuffleProof>, // 現在（直前）のハンドのシャッフルの証明
     #[serde(skip)] // デッキ情報はクライアントに送らない
-    deck: Vec<String>,
+    deck: Deck,
+    #[serde(skip)] // 進行中のハンドの記録
+    hand_history: HandHistory,
+    #[serde(skip)] // まだ保存していないハンドの結果
+    completed_hands: Vec<HandResult>,
+    #[serde(skip)] // まだ通知していない退席
+    cash_outs: Vec<CashOut>,
+    #[serde(skip, default = "ChaCha20Rng::from_entropy")] // ハンドごとのサーバーシードを作るRNG
+    seed_rng: ChaCha20Rng,
+    #[serde(skip)] // 次のハンドで使うサーバーシード（ハッシュだけ公開している）
+    next_server_seed: [u8; 32],
+    #[serde(skip)] // 進行中のハンドのサーバーシード（ハンドが終わるまで公開しない）
+    hand_server_seed: Option<[u8; 32]>,
+    #[serde(skip)] // 次のハンドで配るデッキ（指定されていればシャッフルしない）
+    preset_deck: Option<Deck>,
 }
 
 impl GameState {
     // 新しいゲームを作成
-    pub fn new() -> Self {
-        GameState {
+    pub fn new(config: TableConfig) -> Self {
+        Self::with_rng(config, ChaCha20Rng::from_entropy())
+    }
+
+    // シードを指定してゲームを作成する。毎ハンドのシャッフルが決まった順になる（テストや不具合の再現用）
+    #[cfg(test)]
+    pub fn with_seed(config: TableConfig, seed: u64) -> Self {
+        Self::with_rng(config, ChaCha20Rng::seed_from_u64(seed))
+    }
+
+    fn with_rng(config: TableConfig, seed_rng: ChaCha20Rng) -> Self {
+        let mut game = GameState {
             players: Vec::new(),
             community_cards: Vec::new(),
             pot: 0,
+            pots: Vec::new(),
             current_turn_username: None,
-            status: "Waiting".to_string(),
+            status: GamePhase::Waiting,
             current_bet: 0,
-            dealer_index: 0,
+            min_raise: config.big_blind,
+            button_seat: None,
+            small_blind_seat: None,
+            big_blind_seat: None,
+            last_aggressor: None,
             winner_message: None,
-            deck: Vec::new(),
-        }
+            tournament: config.tournament.clone().map(TournamentState::new),
+            turn_timer: None,
+            runout_at: None,
+            seed_commitment: String::new(),
+            client_seeds: BTreeMap::new(),
+            shuffle_proof: None,
+            config,
+            deck: Deck::default(),
+            hand_history: HandHistory::default(),
+            completed_hands: Vec::new(),
+            cash_outs: Vec::new(),
+            seed_rng,
+            next_server_seed: [0; 32],
+            hand_server_seed: None,
+            preset_deck: None,
+        };
+        game.commit_next_server_seed();
+        game
+    }
+
+    // 次のハンドのサーバーシードを決め、ハッシュを公開する
+    fn commit_next_server_seed(&mut self) {
+        self.seed_rng.fill(&mut self.next_server_seed);
+        self.seed_commitment = fairness::hash_server_seed(&self.next_server_seed);
     }
 
-    // プレイヤーをゲームに追加
-    pub fn add_player(&mut self, username: String) {
+    // 次のハンドからシャッフルに混ぜるシードを登録する。着席しているプレイヤーだけが送れる
+    pub fn set_client_seed(&mut self, username: &str, seed: String) -> Result<(), GameError> {
         if !self.players.iter().any(|p| p.username == username) {
-            self.players.push(Player {
-                username,
-                stack: 1000, // 初期スタック
+            return Err(GameError::PlayerNotFound);
+        }
+        if seed.is_empty() || seed.chars().count() > MAX_CLIENT_SEED_LEN {
+            return Err(GameError::InvalidClientSeed);
+        }
+        self.client_seeds.insert(username.to_string(), seed);
+        Ok(())
+    }
+
+    // 次のハンドで配るデッキを指定する（テストや、記録したシードからハンドを再現するときに使う）
+    #[cfg(test)]
+    pub fn preset_next_deck(&mut self, deck: Deck) {
+        self.preset_deck = Some(deck);
+    }
+
+    // 指定した席に着席する
+    pub fn take_seat(&mut self, username: &str, seat: usize) -> Result<(), GameError> {
+        if self.players.iter().any(|p| p.username == username) {
+            return Err(GameError::AlreadySeated);
+        }
+        if seat >= self.config.max_seats {
+            return Err(GameError::InvalidSeat);
+        }
+        if self.players.iter().any(|p| p.seat == seat) {
+            return Err(GameError::SeatTaken);
+        }
+        // トーナメントは開始後の途中参加を認めない
+        if self.tournament.as_ref().is_some_and(|t| t.is_started()) {
+            return Err(GameError::TournamentInProgress);
+        }
+
+        let index = self.players.partition_point(|p| p.seat < seat);
+        self.players.insert(
+            index,
+            Player {
+                username: username.to_string(),
+                seat,
+                stack: self.config.starting_stack, // 初期スタック
                 hand: Vec::new(),
                 is_active: false,
                 current_bet: 0,
-            });
+                total_bet: 0,
+                is_all_in: false,
+                has_acted: false,
+                time_bank_secs: self.config.time_bank_secs,
+                disconnected_at: None,
+                is_sitting_out: false,
+                is_leaving: false,
+                missed_small_blind: false,
+                missed_big_blind: false,
+                shows_hand: false,
+            },
+        );
+        Ok(())
+    }
+
+    // 再接続したプレイヤーを席に戻す。着席していればtrueを返す
+    pub fn reconnect(&mut self, username: &str) -> bool {
+        match self.players.iter_mut().find(|p| p.username == username) {
+            Some(player) => {
+                player.disconnected_at = None;
+                true
+            }
+            None => false,
         }
     }
 
-    // ゲームを開始する
-    pub fn start_game(&mut self) {
-        if self.status != "Waiting" || self.players.len() < 2 {
-            return; // 待機中でなければ開始しない
+    // 席を立つ。ハンドに参加中なら、そのハンドが終わってから席を外す
+    fn leave_table(&mut self, player_index: usize) -> Result<(), GameError> {
+        if self
+            .tournament
+            .as_ref()
+            .is_some_and(|t| t.is_started() && !t.is_finished)
+        {
+            return Err(GameError::TournamentInProgress);
         }
-        self.winner_message = None;
+        let player = &mut self.players[player_index];
+        if self.status.is_betting() && !player.hand.is_empty() {
+            player.is_leaving = true;
+            // 手番ならその場でフォールドする（手番でなければ回ってきたときに自動でフォールド）
+            if player.is_active && self.current_turn_username.as_deref() == Some(&player.username) {
+                let username = player.username.clone();
+                return self.handle_action(&username, PlayerAction::Fold);
+            }
+            return Ok(());
+        }
+        self.remove_player(player_index);
+        Ok(())
+    }
 
-        self.dealer_index = (self.dealer_index + 1) % self.players.len();
+    // ハンド終了後に手札を見せる。フォールドした手札は見せられない
+    fn show_cards(&mut self, player_index: usize) -> Result<(), GameError> {
+        if !self.status.is_hand_over() {
+            return Err(GameError::HandNotFinished);
+        }
+        let player = &mut self.players[player_index];
+        if !player.is_active || player.hand.is_empty() {
+            return Err(GameError::NoCardsToShow);
+        }
+        player.shows_hand = true;
+        Ok(())
+    }
 
-        self.deck = create_deck();
-        self.deck.shuffle(&mut thread_rng());
+    // プレイヤーを席から外し、持ち帰るチップを記録する
+    fn remove_player(&mut self, index: usize) {
+        let player = self.players.remove(index);
+        self.client_seeds.remove(&player.username);
+        self.cash_outs.push(CashOut {
+            username: player.username,
+            stack: player.stack,
+        });
+    }
 
-        // 手札を配る
-        for player in &mut self.players {
-            player.hand = vec![self.deck.pop().unwrap(), self.deck.pop().unwrap()];
-            player.is_active = true;
-            player.current_bet = 0;
+    // まだ通知していない退席を取り出す
+    pub fn take_cash_outs(&mut self) -> Vec<CashOut> {
+        std::mem::take(&mut self.cash_outs)
+    }
+
+    // 接続が切れたプレイヤーを離席中にする。猶予時間内に再接続すれば席に戻れる
+    pub fn mark_disconnected(&mut self, username: &str, now: DateTime<Utc>) {
+        if let Some(player) = self.players.iter_mut().find(|p| p.username == username) {
+            player.disconnected_at = Some(now);
         }
+    }
 
-        // ★ ブラインドの処理
-        let small_blind_index = (self.dealer_index + 1) % self.players.len();
-        let big_blind_index = (self.dealer_index + 2) % self.players.len();
+    // 全員が切断して猶予時間を過ぎた（ルームを片付けてよい）か
+    pub fn is_abandoned(&self, now: DateTime<Utc>) -> bool {
+        let grace = Duration::seconds(RECONNECT_GRACE_SECS);
+        self.players
+            .iter()
+            .all(|p| p.disconnected_at.is_some_and(|at| now - at >= grace))
+    }
 
-        let sb_amount = 10;
-        let bb_amount = 20;
+    // 猶予時間を過ぎても戻らないプレイヤーを、ハンドの合間に席から外す。
+    // トーナメントでは順位が決まるまで席を残す（離席中は自動でフォールドされる）
+    pub fn remove_expired_players(&mut self, now: DateTime<Utc>) -> bool {
+        if self.status != GamePhase::Waiting || self.tournament.is_some() {
+            return false;
+        }
+        let grace = Duration::seconds(RECONNECT_GRACE_SECS);
+        let mut removed = false;
+        let mut index = 0;
+        while index < self.players.len() {
+            let expired = self.players[index]
+                .disconnected_at
+                .is_some_and(|at| now - at >= grace);
+            if expired {
+                self.remove_player(index);
+                removed = true;
+            } else {
+                index += 1;
+            }
+        }
+        removed
+    }
 
-        // スモールブラインド
-        let sb_player = &mut self.players[small_blind_index];
-        let sb_bet = std::cmp::min(sb_amount, sb_player.stack);
-        sb_player.stack -= sb_bet;
-        sb_player.current_bet = sb_bet;
-        self.pot += sb_bet;
+    // ゲームを開始する
+    pub fn start_game(&mut self) -> Result<(), GameError> {
+        if self.status == GamePhase::Finished {
+            return Err(GameError::TournamentFinished);
+        }
+        if self.status != GamePhase::Waiting {
+            return Err(GameError::GameAlreadyStarted); // 待機中でなければ開始しない
+        }
+        let dealt_in: Vec<bool> = self.players.iter().map(|p| self.is_dealt_in(p)).collect();
+        // 始まったトーナメントは、敗退者が出て人数が減っても残りの2人以上で続ける
+        let required = if self.tournament.as_ref().is_some_and(|t| t.is_started()) {
+            2
+        } else {
+            self.config.min_players
+        };
+        if dealt_in.iter().filter(|&&d| d).count() < required {
+            return Err(GameError::NotEnoughPlayers { required });
+        }
+        // ボタンとブラインドを次の位置へ進める
+        let previous_big_blind_seat = self.big_blind_seat;
+        let (small_blind_index, big_blind_index) = self.move_button(&dealt_in);
+        self.record_missed_blinds(previous_big_blind_seat);
 
-        // ビッグブラインド
-        let bb_player = &mut self.players[big_blind_index];
-        let bb_bet = std::cmp::min(bb_amount, bb_player.stack);
-        bb_player.stack -= bb_bet;
-        bb_player.current_bet = bb_bet;
-        self.pot += bb_bet;
+        // トーナメントなら経過に応じてブラインドレベルを上げる
+        if let Some(tournament) = &mut self.tournament {
+            tournament.update_level(Utc::now());
+        }
+        let blinds = self.current_blinds();
 
-        self.current_bet = bb_amount;
-        self.status = "Pre-flop".to_string();
+        // ハッシュを公開済みのサーバーシードにプレイヤーのシードを混ぜてシャッフルし、
+        // 次のハンドのサーバーシードを新しく決める。シードは記録に残す
+        let seed = match self.preset_deck.take() {
+            Some(deck) => {
+                self.deck = deck;
+                self.hand_server_seed = None;
+                self.shuffle_proof = None;
+                None
+            }
+            None => {
+                let server_seed = self.next_server_seed;
+                let client_seeds: Vec<ClientSeed> = self
+                    .client_seeds
+                    .iter()
+                    .map(|(username, seed)| ClientSeed {
+                        username: username.clone(),
+                        seed: seed.clone(),
+                    })
+                    .collect();
+                self.deck = fairness::create_deck(&server_seed, &client_seeds);
+                self.hand_server_seed = Some(server_seed);
+                self.shuffle_proof = Some(ShuffleProof {
+                    server_seed_hash: self.seed_commitment.clone(),
+                    server_seed: None,
+                    client_seeds: client_seeds.clone(),
+                });
+                self.commit_next_server_seed();
+                Some(fairness::deck_seed(&server_seed, &client_seeds))
+            }
+        };
 
-        // BBの次の人からアクション開始
-        self.current_turn_username = Some(
-            self.players[(big_blind_index + 1) % self.players.len()]
-                .username
-                .clone(),
-        );
-    }
+        self.hand_history = HandHistory {
+            started_at: Utc::now(),
+            dealer: self
+                .players
+                .iter()
+                .find(|p| Some(p.seat) == self.button_seat)
+                .map(|p| p.username.clone())
+                .unwrap_or_default(),
+            button_seat: self.button_seat,
+            small_blind: blinds.small_blind,
+            big_blind: blinds.big_blind,
+            ante: blinds.ante,
+            seed: seed.map(hex::encode),
+            seats: self
+                .players
+                .iter()
+                .zip(&dealt_in)
+                .filter(|(_, dealt_in)| **dealt_in)
+                .map(|(p, _)| Seat {
+                    seat: p.seat,
+                    username: p.username.clone(),
+                    stack: p.stack,
+                })
+                .collect(),
+            ..Default::default()
+        };
 
-    // プレイヤーのアクションを処理する
-    pub fn handle_action(&mut self, username: &str, action: PlayerAction) {
-        if self.current_turn_username.as_deref() != Some(username) {
-            return;
+        // 手札を配る（チップが残っていて、休憩中でないプレイヤーのみ参加）
+        for (player, _) in self.players.iter_mut().zip(&dealt_in).filter(|(_, d)| **d) {
+            player.hand = vec![self.deck.deal(), self.deck.deal()];
+            player.is_active = true;
+            self.hand_history.events.push(HandEvent::DealHoleCards {
+                username: player.username.clone(),
+                cards: player.hand.clone(),
+            });
         }
 
-        if let PlayerAction::NextHand = action {
-            if self.status == "Showdown" {
-                self.status = "Waiting".to_string();
-                for p in &mut self.players {
-                    p.hand.clear();
+        // アンティ（全員から集める。ベット額には含めない）
+        if blinds.ante > 0 {
+            for index in 0..self.players.len() {
+                let paid = self.post_ante(index, blinds.ante);
+                if paid > 0 {
+                    self.hand_history.events.push(HandEvent::PostAnte {
+                        username: self.players[index].username.clone(),
+                        amount: paid,
+                    });
                 }
             }
-            return;
         }
 
+        // スモールブラインド（スタックが足りなければオールイン。デッドスモールなら誰も払わない）
+        if let Some(small_blind_index) = small_blind_index {
+            let paid = self.commit_chips(small_blind_index, blinds.small_blind);
+            if paid > 0 {
+                self.hand_history.events.push(HandEvent::PostSmallBlind {
+                    username: self.players[small_blind_index].username.clone(),
+                    amount: paid,
+                });
+            }
+        }
+
+        // ビッグブラインド
+        let paid = self.commit_chips(big_blind_index, blinds.big_blind);
+        if paid > 0 {
+            self.hand_history.events.push(HandEvent::PostBigBlind {
+                username: self.players[big_blind_index].username.clone(),
+                amount: paid,
+            });
+        }
+
+        // 休憩から戻ったプレイヤーは払わなかったブラインドを払う
+        // （BBはライブでベット額に数え、SBはデッドとしてポットに入れる）
+        for index in 0..self.players.len() {
+            let player = &mut self.players[index];
+            if !player.is_active {
+                continue;
+            }
+            let (missed_small, missed_big) = (player.missed_small_blind, player.missed_big_blind);
+            player.missed_small_blind = false;
+            player.missed_big_blind = false;
+            if Some(index) == small_blind_index || index == big_blind_index {
+                continue;
+            }
+            let live = if missed_big {
+                self.commit_chips(index, blinds.big_blind)
+            } else {
+                0
+            };
+            let dead = if missed_small {
+                self.post_ante(index, blinds.small_blind)
+            } else {
+                0
+            };
+            if live > 0 || dead > 0 {
+                self.hand_history.events.push(HandEvent::PostMissedBlinds {
+                    username: self.players[index].username.clone(),
+                    live,
+                    dead,
+                });
+            }
+        }
+
+        self.current_bet = blinds.big_blind;
+        self.min_raise = blinds.big_blind;
+        self.transition_to(GamePhase::PreFlop);
+
+        // BBの次の人からアクション開始（ヘッズアップではボタン＝SBから）
+        self.current_turn_username = self.next_actor_after(big_blind_index);
+
+        // ブラインドだけで全員オールインになった場合はそのまま最後まで進める
+        if self.check_betting_round_over() {
+            self.proceed_to_next_stage();
+        }
+        self.reset_turn_timer(Utc::now());
+        Ok(())
+    }
+
+    // プレイヤーのアクションを処理する
+    pub fn handle_action(&mut self, username: &str, action: PlayerAction) -> Result<(), GameError> {
+        // 着席は席についていないプレイヤーが行う
+        if let PlayerAction::TakeSeat { seat } = action
+            && !self.players.iter().any(|p| p.username == username)
+        {
+            return self.take_seat(username, seat);
+        }
         let player_index = self
             .players
             .iter()
             .position(|p| p.username == username)
-            .unwrap();
+            .ok_or(GameError::PlayerNotFound)?;
+
+        // ハンドの外で行う操作は手番に関係なく受け付ける
+        match action {
+            PlayerAction::StartGame => return self.start_game(),
+            PlayerAction::TakeSeat { .. } => return Err(GameError::AlreadySeated),
+            PlayerAction::SitOut => {
+                self.players[player_index].is_sitting_out = true;
+                return Ok(());
+            }
+            PlayerAction::SitIn => {
+                self.players[player_index].is_sitting_out = false;
+                return Ok(());
+            }
+            PlayerAction::LeaveTable => return self.leave_table(player_index),
+            PlayerAction::ShowCards => return self.show_cards(player_index),
+            PlayerAction::NextHand => {
+                if !self.status.is_hand_over() {
+                    return Err(GameError::HandNotFinished);
+                }
+                if self.tournament.as_ref().is_some_and(|t| t.is_finished) {
+                    self.transition_to(GamePhase::Finished);
+                } else {
+                    self.transition_to(GamePhase::Waiting);
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        if self.current_turn_username.as_deref() != Some(username) {
+            return Err(GameError::NotYourTurn);
+        }
+
+        let legal = self.legal_actions_for(player_index);
+        let to_call = self.current_bet - self.players[player_index].current_bet;
+        let stack_before = self.players[player_index].stack;
+        let street = self.status;
 
         match action {
             PlayerAction::Fold => {
                 self.players[player_index].is_active = false;
             }
+            PlayerAction::Check => {
+                if !legal.can_check {
+                    return Err(GameError::CannotCheck); // コールが必要な場面ではチェックできない
+                }
+            }
             PlayerAction::Call => {
-                let to_call = self.current_bet - self.players[player_index].current_bet;
-                if to_call > 0 && self.players[player_index].stack >= to_call {
-                    let player = &mut self.players[player_index];
-                    player.stack -= to_call;
-                    player.current_bet += to_call;
-                    self.pot += to_call;
+                if legal.call_amount.is_none() {
+                    return Err(GameError::NothingToCall); // コールする額がない場合はチェックすべき
                 }
+                // スタックが足りない場合は残り全額でコール（オールイン）
+                self.commit_chips(player_index, to_call);
             }
             PlayerAction::Bet { amount } => {
-                // ベット額が現在のベット額以上か、かつスタックの範囲内かチェック
-                if amount >= self.current_bet
-                    && self.players[player_index].stack
-                        >= (amount - self.players[player_index].current_bet)
-                {
-                    let player = &mut self.players[player_index];
-                    let bet_increase = amount - player.current_bet;
-                    player.stack -= bet_increase;
-                    player.current_bet = amount;
-                    self.pot += bet_increase;
-                    self.current_bet = amount;
+                let range = legal.bet.ok_or(GameError::CannotBet)?;
+                if !range.contains(amount) {
+                    return Err(GameError::InvalidBet {
+                        min: range.min,
+                        max: range.max,
+                    });
+                }
+                self.raise_to(player_index, amount);
+            }
+            PlayerAction::Raise { to } => {
+                let range = legal.raise.ok_or(GameError::CannotRaise)?;
+                if !range.contains(to) {
+                    return Err(GameError::InvalidRaise {
+                        min: range.min,
+                        max: range.max,
+                    });
+                }
+                self.raise_to(player_index, to);
+            }
+            PlayerAction::AllIn => {
+                // レイズ権がない場合はコール額を超えるオールインはできない
+                let all_in_to = legal.all_in.ok_or(GameError::CannotRaise)?;
+                if all_in_to <= self.current_bet {
+                    // コール額に届かない（または丁度の）オールインはコール扱い
+                    self.commit_chips(player_index, to_call);
                 } else {
-                    return; // 無効なベット
+                    self.raise_to(player_index, all_in_to);
                 }
             }
-            _ => {}
+            PlayerAction::StartGame
+            | PlayerAction::NextHand
+            | PlayerAction::TakeSeat { .. }
+            | PlayerAction::SitOut
+            | PlayerAction::SitIn
+            | PlayerAction::LeaveTable
+            | PlayerAction::ShowCards => unreachable!(),
         }
+        let player = &mut self.players[player_index];
+        player.has_acted = true;
+        self.hand_history.events.push(HandEvent::PlayerAction {
+            street,
+            username: player.username.clone(),
+            action,
+            chips: stack_before - player.stack,
+            is_all_in: player.is_all_in,
+        });
+        let now = Utc::now();
+        self.consume_time_bank(player_index, now);
 
         // ハンドが終了したかチェック
-        if self.check_hand_over() {
-            return;
+        if !self.check_hand_over() {
+            // ベッティングラウンド終了チェック
+            if self.check_betting_round_over() {
+                self.proceed_to_next_stage();
+            } else {
+                self.advance_turn();
+            }
         }
+        self.reset_turn_timer(now);
+        Ok(())
+    }
 
-        // ベッティングラウンド終了チェック
-        if self.check_betting_round_over() {
-            self.proceed_to_next_stage();
-        } else {
-            self.advance_turn();
+    // 持ち時間切れと離席中のプレイヤーを処理する。状態が変わった場合はtrueを返す
+    // 持ち時間が切れたらまずタイムバンクを使い、それも切れたらチェック（できなければフォールド）する。
+    // 離席中・休憩中のプレイヤーは手番が来たらすぐにチェック（フォールド）し、
+    // 退席を申し出たプレイヤーはすぐにフォールドする
+    pub fn handle_timeout(&mut self, now: DateTime<Utc>) -> bool {
+        let Some(username) = self.current_turn_username.clone() else {
+            return false;
+        };
+        let Some(player) = self.players.iter().find(|p| p.username == username) else {
+            return false;
+        };
+
+        if player.is_leaving {
+            return self.handle_action(&username, PlayerAction::Fold).is_ok();
+        }
+
+        if player.disconnected_at.is_none() && !player.is_sitting_out {
+            let Some(timer) = &mut self.turn_timer else {
+                return false;
+            };
+            if now < timer.deadline {
+                return false;
+            }
+
+            if !timer.using_time_bank && player.time_bank_secs > 0 {
+                timer.using_time_bank = true;
+                timer.time_bank_started_at = Some(now);
+                timer.deadline = now + Duration::seconds(player.time_bank_secs as i64);
+                return true;
+            }
+
+            // タイムバンクを使い切った
+            if timer.using_time_bank {
+                timer.time_bank_started_at = None;
+                if let Some(player) = self.players.iter_mut().find(|p| p.username == username) {
+                    player.time_bank_secs = 0;
+                }
+            }
+        }
+
+        let action = match self.legal_actions() {
+            Some(legal) if legal.can_check => PlayerAction::Check,
+            _ => PlayerAction::Fold,
+        };
+        self.handle_action(&username, action).is_ok()
+    }
+
+    // 手番が移ったら（同じプレイヤーに再び回った場合も）持ち時間をリセットする
+    fn reset_turn_timer(&mut self, now: DateTime<Utc>) {
+        self.turn_timer = match &self.current_turn_username {
+            Some(username) if self.config.action_timeout_secs > 0 => Some(TurnTimer {
+                username: username.clone(),
+                deadline: now + Duration::seconds(self.config.action_timeout_secs as i64),
+                using_time_bank: false,
+                time_bank_started_at: None,
+            }),
+            _ => None,
+        };
+    }
+
+    // タイムバンク使用中にアクションした場合、使った分をタイムバンクから差し引く
+    fn consume_time_bank(&mut self, player_index: usize, now: DateTime<Utc>) {
+        let Some(started_at) = self
+            .turn_timer
+            .as_ref()
+            .and_then(|timer| timer.time_bank_started_at)
+        else {
+            return;
+        };
+        let used_secs = (now - started_at).num_seconds().max(0) as u32;
+        let player = &mut self.players[player_index];
+        player.time_bank_secs = player.time_bank_secs.saturating_sub(used_secs);
+    }
+
+    // 手番のプレイヤーが取れるアクションの一覧を返す
+    pub fn legal_actions(&self) -> Option<LegalActions> {
+        let username = self.current_turn_username.as_deref()?;
+        let player_index = self.players.iter().position(|p| p.username == username)?;
+        Some(self.legal_actions_for(player_index))
+    }
+
+    fn legal_actions_for(&self, player_index: usize) -> LegalActions {
+        let player = &self.players[player_index];
+        let to_call = self.current_bet - player.current_bet;
+        let max_bet = player.current_bet + player.stack; // オールイン時のベット額
+        let can_raise = self.can_raise(player_index);
+
+        // ベットはまだ誰もベットしていないときのみ。最低額はBB（オールインを除く）
+        let bet = (self.current_bet == 0 && player.stack > 0).then(|| BetRange {
+            min: self.current_blinds().big_blind.min(max_bet),
+            max: max_bet,
+        });
+        // レイズ後の額は現在のベット＋最低レイズ幅以上（オールインを除く）
+        let raise = (self.current_bet > 0 && can_raise).then(|| BetRange {
+            min: (self.current_bet + self.min_raise).min(max_bet),
+            max: max_bet,
+        });
+        // コール額以下のオールインはいつでも可能。それを超える場合はレイズ権が必要
+        let all_in = (player.stack > 0
+            && (max_bet <= self.current_bet || self.current_bet == 0 || can_raise))
+            .then_some(max_bet);
+
+        LegalActions {
+            username: player.username.clone(),
+            can_fold: true,
+            can_check: to_call == 0,
+            call_amount: (to_call > 0).then(|| to_call.min(player.stack)),
+            bet,
+            raise,
+            all_in,
         }
     }
 
     fn proceed_to_next_stage(&mut self) {
+        self.return_uncalled_bet();
         // 次のラウンドの準備
         self.current_bet = 0;
+        self.min_raise = self.current_blinds().big_blind;
         for p in &mut self.players {
+            // フォールドしたプレイヤーのベットも、コールされた額として次のラウンドに残さない
+            p.current_bet = 0;
             if p.is_active {
-                p.current_bet = 0;
+                p.has_acted = false;
             }
         }
-        // ディーラーの次のアクティブなプレイヤーからターンを再開
-        self.current_turn_username = self
-            .players
-            .iter()
-            .cycle()
-            .skip(self.dealer_index + 1)
-            .find(|p| p.is_active)
-            .map(|p| p.username.clone());
-
-        match self.status.as_str() {
-            "Pre-flop" => {
-                self.status = "Flop".to_string();
-                self.community_cards.push(self.deck.pop().unwrap());
-                self.community_cards.push(self.deck.pop().unwrap());
-                self.community_cards.push(self.deck.pop().unwrap());
-            }
-            "Flop" => {
-                self.status = "Turn".to_string();
-                self.community_cards.push(self.deck.pop().unwrap());
-            }
-            "Turn" => {
-                self.status = "River".to_string();
-                self.community_cards.push(self.deck.pop().unwrap());
-            }
-            "River" => {
-                self.status = "Showdown".to_string();
-                self.determine_winner(); // 勝者判定
+        self.pots = self.build_pots();
+
+        // アクションできるプレイヤーが1人以下（残りは全員オールイン）なら、これ以上ベットは発生しない。
+        // 全員の手札を公開し、残りのストリートは間を置いて自動で配る（advance_runoutで進める）
+        if self.count_players_able_to_act() < 2 {
+            for player in self.players.iter_mut().filter(|p| p.is_active) {
+                player.shows_hand = true;
+            }
+            if self.status != GamePhase::River {
+                self.current_turn_username = None;
+                self.runout_at = Some(Utc::now() + Duration::seconds(RUNOUT_DELAY_SECS));
+                return;
             }
-            _ => {}
         }
+        self.deal_next_street();
     }
 
-    // 勝者を決定する（簡易的なハイカード判定）
-    fn determine_winner(&mut self) {
-        let mut best_rank: Option<HandRank> = None;
-        let mut winners: Vec<String> = Vec::new();
+    // 次のストリートのカードを配る。リバーの次はショーダウンで勝者を決める
+    fn deal_next_street(&mut self) {
+        // ボタンの次のアクション可能なプレイヤーからターンを再開（ヘッズアップではBBから）
+        let button_seat = self.button_seat.unwrap_or_default();
+        self.current_turn_username =
+            self.next_actor_after(self.index_at_or_before_seat(button_seat));
 
-        for player in self.players.iter().filter(|p| p.is_active) {
-            let mut seven_cards_str: Vec<String> = self.community_cards.clone();
-            seven_cards_str.extend(player.hand.clone());
-
-            let cards = hand_evaluator::parse_cards(&seven_cards_str);
-            if let Some(current_rank) = hand_evaluator::evaluate_hand(&cards) {
-                if best_rank.is_none() || current_rank > *best_rank.as_ref().unwrap() {
-                    best_rank = Some(current_rank);
-                    winners.clear();
-                    winners.push(player.username.clone());
-                } else if current_rank == *best_rank.as_ref().unwrap() {
-                    // 引き分けの場合
-                    winners.push(player.username.clone());
-                }
+        let Some(next_phase) = self.status.next_street() else {
+            return;
+        };
+        self.transition_to(next_phase);
+        let dealt_count = match next_phase {
+            GamePhase::Flop => 3,
+            GamePhase::Turn | GamePhase::River => 1,
+            _ => {
+                self.determine_winner(); // 勝者判定
+                return;
             }
-        }
+        };
+        // ショーダウンの順番を決めるため、最後のアグレッサーはリバーの後は残しておく
+        self.last_aggressor = None;
+        let cards: Vec<Card> = (0..dealt_count).map(|_| self.deck.deal()).collect();
+        self.community_cards.extend_from_slice(&cards);
+        self.hand_history.events.push(HandEvent::DealBoard {
+            street: next_phase,
+            cards,
+        });
+    }
 
-        if !winners.is_empty() {
-            let pot_share = self.pot / winners.len() as u32;
-            let winner_names = winners.join(", ");
-            let message = format!(
-                "{}が{}でポット{}を獲得しました。",
-                winner_names,
-                best_rank.as_ref().unwrap(),
-                self.pot
-            );
-            self.winner_message = Some(message);
-            for winner_name in winners {
-                if let Some(winner_player) =
-                    self.players.iter_mut().find(|p| p.username == winner_name)
-                {
-                    winner_player.stack += pot_share;
-                }
-            }
+    // 全員オールインのランアウト中、時刻が来たら次のストリートを配る。状態が変わった場合はtrueを返す
+    pub fn advance_runout(&mut self, now: DateTime<Utc>) -> bool {
+        if self.runout_at.is_none_or(|at| now < at) {
+            return false;
         }
-
-        self.current_turn_username = None;
+        self.runout_at = None;
+        self.deal_next_street();
+        if self.status.is_betting() {
+            self.current_turn_username = None;
+            self.runout_at = Some(now + Duration::seconds(RUNOUT_DELAY_SECS));
+        }
+        true
     }
 
-    // ベッティングラウンドが終了したか判定
-    fn check_betting_round_over(&self) -> bool {
-        // アクティブなプレイヤー全員が同じ額をベットしていればラウンド終了
-        self.players
+    // ラウンドの終わりに、誰にもコールされなかった分のベットを本人に返す。
+    // 返した分はポットに入らないので、ひとりだけのサイドポットにはならない
+    fn return_uncalled_bet(&mut self) {
+        let Some(top_index) = (0..self.players.len()).max_by_key(|&i| self.players[i].current_bet)
+        else {
+            return;
+        };
+        let called = self
+            .players
             .iter()
-            .filter(|p| p.is_active)
-            .all(|p| p.current_bet == self.current_bet)
+            .enumerate()
+            .filter(|&(i, _)| i != top_index)
+            .map(|(_, p)| p.current_bet)
+            .max()
+            .unwrap_or(0);
+        let player = &mut self.players[top_index];
+        let amount = player.current_bet - called;
+        if amount == 0 {
+            return;
+        }
+        player.current_bet -= amount;
+        player.total_bet -= amount;
+        player.stack += amount;
+        player.is_all_in = false;
+        self.pot -= amount;
+        self.hand_history.events.push(HandEvent::ReturnUncalledBet {
+            username: player.username.clone(),
+            amount,
+        });
     }
 
-    // ハンドが終了したかチェックし、終了していればポットを勝者に渡す
-    fn check_hand_over(&mut self) -> bool {
-        let active_players: Vec<_> = self.players.iter().filter(|p| p.is_active).collect();
-        if active_players.len() == 1 {
-            let winner_username = active_players[0].username.clone();
+    // 各プレイヤーの投入額からメインポットとサイドポットを組み立てる
+    pub fn build_pots(&self) -> Vec<Pot> {
+        // フォールドしていないプレイヤーの投入額が、ポットを区切るレベルになる
+        let mut levels: Vec<u32> = self
+            .players
+            .iter()
+            .filter(|p| p.is_active && p.total_bet > 0)
+            .map(|p| p.total_bet)
+            .collect();
+        levels.sort_unstable();
+        levels.dedup();
+
+        let mut pots: Vec<Pot> = Vec::new();
+        let mut previous_level = 0;
+        for level in levels {
+            let amount: u32 = self
+                .players
+                .iter()
+                .map(|p| p.total_bet.min(level) - p.total_bet.min(previous_level))
+                .sum();
+            let eligible_players: Vec<String> = self
+                .players
+                .iter()
+                .filter(|p| p.is_active && p.total_bet >= level)
+                .map(|p| p.username.clone())
+                .collect();
+            previous_level = level;
+
+            // 参加者が同じならひとつのポットにまとめる
+            match pots.last_mut() {
+                Some(last) if last.eligible_players == eligible_players => {
+                    last.amount += amount;
+                }
+                _ => pots.push(Pot {
+                    amount,
+                    eligible_players,
+                }),
+            }
+        }
+
+        // フォールドしたプレイヤーが最大レベルを超えて投入した分は最後のポットに加える
+        let leftover: u32 = self
+            .players
+            .iter()
+            .map(|p| p.total_bet - p.total_bet.min(previous_level))
+            .sum();
+        if let Some(last) = pots.last_mut() {
+            last.amount += leftover;
+        }
+        pots
+    }
+
+    // 勝者を決定し、ポットごとに獲得権利のあるプレイヤーで分配する
+    fn determine_winner(&mut self) {
+        let mut ranks: HashMap<String, HandRank> = HashMap::new();
+        for player in self.players.iter().filter(|p| p.is_active) {
+            let mut seven_cards: Vec<Card> = self.community_cards.clone();
+            seven_cards.extend_from_slice(&player.hand);
+            if let Some(rank) = hand_evaluator::evaluate_hand(&seven_cards) {
+                ranks.insert(player.username.clone(), rank);
+            }
+        }
+
+        // ポットごとの勝者（端数のチップを配る順に、ボタンの左から並べる）
+        self.pots = self.build_pots();
+        let mut pot_winners: Vec<Vec<String>> = Vec::new();
+        for pot in &self.pots {
+            let best_rank = pot
+                .eligible_players
+                .iter()
+                .filter_map(|username| ranks.get(username))
+                .max();
+            let mut winners: Vec<String> = pot
+                .eligible_players
+                .iter()
+                .filter(|username| best_rank.is_some() && ranks.get(*username) == best_rank)
+                .cloned()
+                .collect();
+            winners.sort_by_key(|username| self.seats_from_button(username));
+            pot_winners.push(winners);
+        }
+        let all_winners: Vec<String> = self
+            .players
+            .iter()
+            .filter(|p| pot_winners.iter().flatten().any(|w| *w == p.username))
+            .map(|p| p.username.clone())
+            .collect();
+
+        // 最後のストリートでベット・レイズしたプレイヤーから（いなければボタンの左から）順に見せる。
+        // それまでに見せられた手より弱く、どのポットも取れない手はマックする
+        let mut showdown_order: Vec<usize> = (0..self.players.len())
+            .filter(|&i| ranks.contains_key(&self.players[i].username))
+            .collect();
+        showdown_order.sort_by_key(|&i| self.seats_from_button(&self.players[i].username));
+        if let Some(first) = showdown_order
+            .iter()
+            .position(|&i| self.last_aggressor.as_ref() == Some(&self.players[i].username))
+        {
+            showdown_order.rotate_left(first);
+        }
+        let mut best_shown: Option<&HandRank> = None;
+        for index in showdown_order {
+            let player = &mut self.players[index];
+            let rank = &ranks[&player.username];
+            if best_shown.is_none_or(|best| rank >= best) || all_winners.contains(&player.username)
+            {
+                player.shows_hand = true;
+            }
+            if player.shows_hand {
+                best_shown = best_shown.max(Some(rank));
+                self.hand_history.events.push(HandEvent::Showdown {
+                    username: player.username.clone(),
+                    cards: player.hand.clone(),
+                    hand_rank: rank.to_string(),
+                });
+            } else {
+                self.hand_history.events.push(HandEvent::MuckCards {
+                    username: player.username.clone(),
+                });
+            }
+        }
+
+        let mut messages: Vec<String> = Vec::new();
+        let mut winning_hand_rank: Option<String> = None;
+        for (pot_index, (pot, winners)) in self.pots.iter().zip(&pot_winners).enumerate() {
+            let Some(best_rank) = winners.first().map(|w| &ranks[w]) else {
+                continue;
+            };
+            let pot_name = if pot_index == 0 {
+                "メインポット".to_string()
+            } else {
+                format!("サイドポット{}", pot_index)
+            };
+            messages.push(format!(
+                "{}が{}で{}{}を獲得しました。",
+                winners.join(", "),
+                best_rank,
+                pot_name,
+                pot.amount
+            ));
+            if pot_index == 0 {
+                winning_hand_rank = Some(best_rank.to_string());
+            }
+
+            // 割り切れない端数のチップは、ボタンの左から数えて最初の勝者から1枚ずつ配る
+            let pot_share = pot.amount / winners.len() as u32;
+            let odd_chips = pot.amount as usize % winners.len();
+            for (i, winner_name) in winners.iter().enumerate() {
+                let amount = pot_share + u32::from(i < odd_chips);
+                if let Some(winner_player) =
+                    self.players.iter_mut().find(|p| p.username == *winner_name)
+                {
+                    winner_player.stack += amount;
+                }
+                self.hand_history.events.push(HandEvent::CollectPot {
+                    username: winner_name.clone(),
+                    amount,
+                    pot_index,
+                });
+            }
+        }
+        self.pot = 0;
+        self.pots.clear();
+
+        if !messages.is_empty() {
+            self.winner_message = Some(messages.join(" "));
+        }
+
+        self.current_turn_username = None;
+        // 記録する役は公開された手だけ
+        let showdown_ranks: HashMap<String, String> = self
+            .players
+            .iter()
+            .filter(|p| p.shows_hand)
+            .filter_map(|p| Some((p.username.clone(), ranks.get(&p.username)?.to_string())))
+            .collect();
+        self.record_hand_result(winning_hand_rank, &showdown_ranks, &all_winners);
+        self.record_hand_finished();
+    }
+
+    // 終了したハンドの結果を、保存待ちの一覧に追加する
+    fn record_hand_result(
+        &mut self,
+        winning_hand_rank: Option<String>,
+        showdown_ranks: &HashMap<String, String>,
+        winners: &[String],
+    ) {
+        let mut history = std::mem::take(&mut self.hand_history);
+        history.board = self.community_cards.clone();
+        // ハンドが終わったのでサーバーシードを公開する
+        if let (Some(proof), Some(server_seed)) =
+            (&mut self.shuffle_proof, self.hand_server_seed.take())
+        {
+            proof.server_seed = Some(hex::encode(server_seed));
+        }
+        history.shuffle = self.shuffle_proof.clone();
+
+        let mut pot = 0;
+        let mut chips_before: u64 = 0;
+        let mut chips_after: u64 = 0;
+        let mut players = Vec::new();
+        for seat in &history.seats {
+            let Some(player) = self.players.iter().find(|p| p.username == seat.username) else {
+                continue;
+            };
+            pot += player.total_bet;
+            chips_before += seat.stack as u64;
+            chips_after += player.stack as u64;
+            players.push(HandResultPlayer {
+                username: seat.username.clone(),
+                net_chips: player.stack as i64 - seat.stack as i64,
+                hand_rank: showdown_ranks.get(&seat.username).cloned(),
+                is_winner: winners.contains(&seat.username),
+            });
+        }
+        // ハンドの前後でテーブル上のチップの合計は変わらない（ポットは全額配り終えている）
+        check_invariant(
+            (chips_after, self.pot) == (chips_before, 0),
+            format_args!(
+                "Chips were not conserved in the hand: before {}, after {}, pot left {}",
+                chips_before, chips_after, self.pot
+            ),
+        );
+        self.completed_hands.push(HandResult {
+            pot,
+            winning_hand_rank,
+            players,
+            history,
+        });
+    }
+
+    // 保存待ちのハンド結果を取り出す
+    pub fn take_completed_hands(&mut self) -> Vec<HandResult> {
+        std::mem::take(&mut self.completed_hands)
+    }
+
+    // 現在のブラインド・アンティ（トーナメントなら現在のレベル）
+    pub fn current_blinds(&self) -> BlindLevel {
+        match &self.tournament {
+            Some(tournament) => tournament.current_level(),
+            None => BlindLevel {
+                small_blind: self.config.small_blind,
+                big_blind: self.config.big_blind,
+                ante: self.config.ante,
+            },
+        }
+    }
+
+    // ハンド終了時の後処理。トーナメントなら敗退者の順位を記録し、優勝者が決まれば終了する
+    fn record_hand_finished(&mut self) {
+        let Some(tournament) = &mut self.tournament else {
+            return;
+        };
+        // このハンドでチップがなくなったプレイヤー（開始時のスタック＝投入額）
+        let busted: Vec<(String, u32)> = self
+            .players
+            .iter()
+            .filter(|p| p.stack == 0 && p.total_bet > 0)
+            .map(|p| (p.username.clone(), p.total_bet))
+            .collect();
+        let survivors: Vec<&Player> = self.players.iter().filter(|p| p.stack > 0).collect();
+        tournament.record_hand(busted, survivors.len());
+
+        if let [winner] = survivors.as_slice() {
+            tournament.finish(winner.username.clone());
+            let message = format!("{}がトーナメントに優勝しました！", winner.username);
+            self.winner_message = Some(match self.winner_message.take() {
+                Some(hand_message) => format!("{} {}", hand_message, message),
+                None => message,
+            });
+        }
+    }
+
+    // ベッティングラウンドが終了したか判定
+    fn check_betting_round_over(&self) -> bool {
+        let mut able_to_act = self.players.iter().filter(|p| p.is_active && !p.is_all_in);
+        // 他の全員がオールインなら、残る1人はベット額に追いついていればアクションする必要がない
+        if self.count_players_able_to_act() <= 1 {
+            return able_to_act.all(|p| p.current_bet >= self.current_bet);
+        }
+        // アクションできる全員が直近のフルレイズ以降にアクションし、ベット額が揃えばラウンド終了
+        // （プリフロップでBBにオプションがあるのも、全員チェックで回るのもこれで判定する）
+        able_to_act.all(|p| p.has_acted && p.current_bet == self.current_bet)
+    }
+
+    // ハンドが終了したかチェックし、終了していればポットを勝者に渡す
+    fn check_hand_over(&mut self) -> bool {
+        let active_players: Vec<_> = self.players.iter().filter(|p| p.is_active).collect();
+        if active_players.len() == 1 {
+            let winner_username = active_players[0].username.clone();
+            self.return_uncalled_bet();
             if let Some(winner) = self
                 .players
                 .iter_mut()
@@ -309,21 +1445,27 @@ impl GameState {
             {
                 winner.stack += self.pot;
             }
-            // ゲーム状態をリセットして次のゲームを待つ
-            self.status = "Waiting".to_string();
-            self.current_turn_username = None;
+            self.winner_message = Some(format!(
+                "{}がポット{}を獲得しました。",
+                winner_username, self.pot
+            ));
+            self.hand_history.events.push(HandEvent::CollectPot {
+                username: winner_username.clone(),
+                amount: self.pot,
+                pot_index: 0,
+            });
             self.pot = 0;
-            self.current_bet = 0;
-            for p in &mut self.players {
-                p.is_active = false;
-                p.hand.clear();
-            }
+            self.pots.clear();
+            self.current_turn_username = None;
+            self.transition_to(GamePhase::HandComplete);
+            self.record_hand_result(None, &HashMap::new(), &[winner_username]);
+            self.record_hand_finished();
             return true;
         }
         false
     }
 
-    // ターンを次のアクティブなプレイヤーに進める
+    // ターンを次のアクション可能なプレイヤーに進める
     fn advance_turn(&mut self) {
         let current_turn_username = match self.current_turn_username.clone() {
             Some(name) => name,
@@ -335,45 +1477,1415 @@ impl GameState {
             .iter()
             .position(|p| p.username == current_turn_username);
 
-        if let Some(index) = current_index {
-            // 次のアクティブなプレイヤーを探す
-            for i in 1..=self.players.len() {
-                let next_index = (index + i) % self.players.len();
-                if self.players[next_index].is_active {
-                    self.current_turn_username = Some(self.players[next_index].username.clone());
-                    return;
+        // アクティブなプレイヤーが一人しかいない場合などはNoneになる
+        self.current_turn_username = current_index.and_then(|index| self.next_actor_after(index));
+    }
+
+    // 次のハンドで手札を配られるか。トーナメントでは休憩中でも配られ、自動でフォールドされる
+    fn is_dealt_in(&self, player: &Player) -> bool {
+        player.stack > 0
+            && !player.is_leaving
+            && (!player.is_sitting_out || self.tournament.is_some())
+    }
+
+    // 指定した位置の次から順に見て、条件を満たす最初のプレイヤーの位置
+    fn next_index_where(&self, index: usize, condition: impl Fn(usize) -> bool) -> usize {
+        (1..=self.players.len())
+            .map(|i| (index + i) % self.players.len())
+            .find(|&i| condition(i))
+            .unwrap_or(index)
+    }
+
+    // ボタンの左を0として時計回りに数えた、プレイヤーの席までの距離
+    fn seats_from_button(&self, username: &str) -> usize {
+        let max_seats = self.config.max_seats;
+        let button_seat = self.button_seat.unwrap_or_default();
+        self.players
+            .iter()
+            .find(|p| p.username == username)
+            .map_or(max_seats, |p| {
+                (p.seat + max_seats - button_seat - 1) % max_seats
+            })
+    }
+
+    // 指定した席か、それより前で最も近い席にいるプレイヤーの位置（いなければ最後の席の位置）。
+    // この位置の次から探せば、指定した席より後ろの席から順に見ることになる
+    fn index_at_or_before_seat(&self, seat: usize) -> usize {
+        self.players
+            .iter()
+            .rposition(|p| p.seat <= seat)
+            .unwrap_or(self.players.len().saturating_sub(1))
+    }
+
+    // ボタンとブラインドの位置を決める（デッドボタン方式）。
+    // BBは前のハンドのBBの次に配られるプレイヤーへ進み、SBは前のハンドのBBの席、
+    // ボタンは前のハンドのSBの席になる。誰もBBを飛ばされず、二度続けて払うこともない。
+    // 席が空いた場合はSBやボタンが空席になる（デッドスモール・デッドボタン）。
+    // ヘッズアップではボタンがSBを払い、プリフロップで最初にアクションする。
+    // 戻り値は（SBを払うプレイヤー、BBを払うプレイヤー）の位置
+    fn move_button(&mut self, dealt_in: &[bool]) -> (Option<usize>, usize) {
+        let heads_up = dealt_in.iter().filter(|&&d| d).count() == 2;
+        let max_seats = self.config.max_seats;
+        let next = |index: usize| self.next_index_where(index, |i| dealt_in[i]);
+        let (button_seat, small_blind_seat, big_blind_index) =
+            match (self.big_blind_seat, self.small_blind_seat) {
+                (Some(previous_big_blind), Some(previous_small_blind)) => {
+                    let big_blind = next(self.index_at_or_before_seat(previous_big_blind));
+                    if heads_up {
+                        // ヘッズアップはBBでない方がボタンとSBを兼ねる
+                        let button = self.players[next(big_blind)].seat;
+                        (button, button, big_blind)
+                    } else {
+                        // 前のBBの席から時計回りに数えた距離
+                        let distance =
+                            |seat: usize| (seat + max_seats - previous_big_blind) % max_seats;
+                        let mut button = previous_small_blind;
+                        // 休憩から戻ったプレイヤーがいるなどで、BBがボタンの席まで回ってしまった場合は
+                        // SBの直前に座っているプレイヤーをボタンにする
+                        if distance(self.players[big_blind].seat) >= distance(button) {
+                            button = self
+                                .players
+                                .iter()
+                                .zip(dealt_in)
+                                .filter(|(_, dealt_in)| **dealt_in)
+                                .map(|(p, _)| p.seat)
+                                .max_by_key(|&seat| distance(seat))
+                                .unwrap_or(button);
+                        }
+                        (button, previous_big_blind, big_blind)
+                    }
+                }
+                // 最初のハンドは席順で最初のプレイヤーがボタン
+                _ => {
+                    let button = next(self.players.len() - 1);
+                    let small_blind = if heads_up { button } else { next(button) };
+                    (
+                        self.players[button].seat,
+                        self.players[small_blind].seat,
+                        next(small_blind),
+                    )
+                }
+            };
+        self.button_seat = Some(button_seat);
+        self.small_blind_seat = Some(small_blind_seat);
+        self.big_blind_seat = Some(self.players[big_blind_index].seat);
+
+        let small_blind_index = self
+            .players
+            .iter()
+            .position(|p| p.seat == small_blind_seat)
+            .filter(|&i| dealt_in[i]);
+        (small_blind_index, big_blind_index)
+    }
+
+    // 休憩中でブラインドを払わなかったプレイヤーを記録する。
+    // 前のハンドのBBの席（今回のSBの席）にいればSBを、
+    // 今回のBBがそこから進む間に飛ばされればBBを払っていない
+    fn record_missed_blinds(&mut self, previous_big_blind_seat: Option<usize>) {
+        if self.tournament.is_some() {
+            return;
+        }
+        let (Some(previous), Some(big_blind_seat)) = (previous_big_blind_seat, self.big_blind_seat)
+        else {
+            return;
+        };
+        let max_seats = self.config.max_seats;
+        let distance = |seat: usize| (seat + max_seats - previous) % max_seats;
+        for player in &mut self.players {
+            if !player.is_sitting_out || player.stack == 0 {
+                continue;
+            }
+            if player.seat == previous {
+                player.missed_small_blind = true;
+            } else if distance(player.seat) < distance(big_blind_seat) {
+                player.missed_big_blind = true;
+            }
+        }
+    }
+
+    // 指定した席の次にいる、アクション可能（フォールドもオールインもしていない）なプレイヤーを探す
+    fn next_actor_after(&self, index: usize) -> Option<String> {
+        (1..=self.players.len())
+            .map(|i| &self.players[(index + i) % self.players.len()])
+            .find(|p| p.is_active && !p.is_all_in)
+            .map(|p| p.username.clone())
+    }
+
+    // まだアクションできるプレイヤーの数
+    fn count_players_able_to_act(&self) -> usize {
+        self.players
+            .iter()
+            .filter(|p| p.is_active && !p.is_all_in)
+            .count()
+    }
+
+    // レイズ（またはベット）する権利があるか。
+    // 直近のフルレイズ以降にすでにアクションしたプレイヤーは、
+    // 最低レイズ幅に満たないオールインに対してレイズし直せない
+    fn can_raise(&self, player_index: usize) -> bool {
+        let player = &self.players[player_index];
+        !player.has_acted && player.current_bet + player.stack > self.current_bet
+    }
+
+    // このラウンドのベット額を`to`まで引き上げる（ベット・レイズ共通）
+    fn raise_to(&mut self, player_index: usize, to: u32) {
+        let increase = to - self.players[player_index].current_bet;
+        self.commit_chips(player_index, increase);
+
+        let raise_size = to - self.current_bet;
+        if raise_size >= self.min_raise {
+            // フルレイズ：他のプレイヤーのアクション（レイズ権）を再開する
+            self.min_raise = raise_size;
+            for (i, p) in self.players.iter_mut().enumerate() {
+                if i != player_index {
+                    p.has_acted = false;
                 }
             }
         }
-        // アクティブなプレイヤーが一人しかいない場合など
+        self.current_bet = to;
+        self.last_aggressor = Some(self.players[player_index].username.clone());
+    }
+
+    // アンティを支払う。ポットには入るが、このラウンドのベット額には数えない
+    fn post_ante(&mut self, player_index: usize, amount: u32) -> u32 {
+        let player = &mut self.players[player_index];
+        if !player.is_active {
+            return 0;
+        }
+        let paid = std::cmp::min(amount, player.stack);
+        player.stack -= paid;
+        player.total_bet += paid;
+        if player.stack == 0 {
+            player.is_all_in = true;
+        }
+        self.pot += paid;
+        paid
+    }
+
+    // プレイヤーのスタックからチップを出す。スタックが足りなければ全額（オールイン）
+    fn commit_chips(&mut self, player_index: usize, amount: u32) -> u32 {
+        let player = &mut self.players[player_index];
+        if !player.is_active {
+            return 0;
+        }
+        let paid = std::cmp::min(amount, player.stack);
+        player.stack -= paid;
+        player.current_bet += paid;
+        player.total_bet += paid;
+        if player.stack == 0 {
+            player.is_all_in = true;
+        }
+        self.pot += paid;
+        paid
+    }
+
+    pub fn status(&self) -> GamePhase {
+        self.status
+    }
+
+    // フェーズを遷移させる。許可されていない遷移はしない
+    fn transition_to(&mut self, next: GamePhase) {
+        if !check_invariant(
+            self.status.can_transition_to(next),
+            format_args!("Invalid phase transition: {:?} -> {:?}", self.status, next),
+        ) {
+            return;
+        }
+        self.status = next;
+        if matches!(next, GamePhase::Waiting | GamePhase::Finished) {
+            self.reset_hand();
+            // ハンド中に退席を申し出たプレイヤーの席を外す
+            while let Some(index) = self.players.iter().position(|p| p.is_leaving) {
+                self.remove_player(index);
+            }
+        }
+    }
+
+    // 次のハンドに向けてテーブルを片付ける
+    fn reset_hand(&mut self) {
+        self.community_cards.clear();
+        self.pot = 0;
+        self.pots.clear();
+        self.current_bet = 0;
+        self.last_aggressor = None;
         self.current_turn_username = None;
+        self.turn_timer = None;
+        self.runout_at = None;
+        self.winner_message = None;
+        self.deck = Deck::default();
+        for p in &mut self.players {
+            p.hand.clear();
+            p.is_active = false;
+            p.current_bet = 0;
+            p.total_bet = 0;
+            p.is_all_in = false;
+            p.has_acted = false;
+            p.shows_hand = false;
+        }
     }
 
     // 他のプレイヤーに手札情報が見えないようにサニタイズ（無害化）したGameStateを返す
     pub fn sanitized(&self) -> Self {
-        if self.status == "Showdown" {
-            return self.clone();
-        }
-
         let mut sanitized_state = self.clone();
         for player in &mut sanitized_state.players {
-            player.hand = Vec::new();
+            // 公開された手札以外は隠す（フォールドした手札やマックした手札は見せない）
+            if !player.shows_hand {
+                player.hand = Vec::new();
+            }
         }
         sanitized_state
     }
 }
 
-// 52枚のカードデッキを作成するヘルパー関数
-fn create_deck() -> Vec<String> {
-    let suits = ["H", "D", "C", "S"]; // Hearts, Diamonds, Clubs, Spades
-    let ranks = [
-        "2", "3", "4", "5", "6", "7", "8", "9", "T", "J", "Q", "K", "A",
-    ];
-    let mut deck = Vec::new();
-    for suit in suits.iter() {
-        for rank in ranks.iter() {
-            deck.push(format!("{}{}", rank, suit));
+// 常に成り立つはずの条件を確かめる。崩れていればバグなので、テストでは即座に検出し、
+// 本番ではログに残して続ける（ルームのタスクを落とさない）。条件が成り立てばtrueを返す
+fn check_invariant(holds: bool, message: fmt::Arguments) -> bool {
+    debug_assert!(holds, "{}", message);
+    if !holds {
+        eprintln!("{}", message);
+    }
+    holds
+}
+
+// カードデッキ。配るカードは末尾から取り出す
+#[derive(Debug, Clone, Default)]
+pub struct Deck {
+    cards: Vec<Card>,
+}
+
+impl Deck {
+    // 52枚の並んだデッキを作成する
+    pub fn new() -> Self {
+        let cards = Suit::ALL
+            .into_iter()
+            .flat_map(|suit| Rank::ALL.into_iter().map(move |rank| Card { rank, suit }))
+            .collect();
+        Deck { cards }
+    }
+
+    pub fn shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
+    }
+
+    // シードから決まる順にシャッフルしたデッキ。同じシードからは必ず同じ順になる
+    pub fn shuffled(seed: [u8; 32]) -> Self {
+        let mut deck = Deck::new();
+        deck.shuffle(&mut ChaCha20Rng::from_seed(seed));
+        deck
+    }
+
+    // 指定したカードを先頭から順に配るデッキ。残りのカードはその後に続く
+    #[cfg(test)]
+    pub fn stacked(top: &[Card]) -> Self {
+        let mut cards: Vec<Card> = Deck::new()
+            .cards
+            .into_iter()
+            .filter(|card| !top.contains(card))
+            .collect();
+        cards.extend(top.iter().rev());
+        Deck { cards }
+    }
+
+    // 配られる順に並べたカード（シャッフルの検証用）
+    pub fn dealing_order(&self) -> Vec<Card> {
+        self.cards.iter().rev().copied().collect()
+    }
+
+    // 1枚配る。1ハンドで使う枚数はデッキの枚数を超えない
+    pub fn deal(&mut self) -> Card {
+        self.cards.pop().expect("deck is empty")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::StatsCounter;
+    use crate::tournament::LevelAdvance;
+
+    // 指定した席にプレイヤー（名前は "p<席番号>"）を座らせたテーブル
+    fn table(seats: &[usize]) -> GameState {
+        let mut game = GameState::new(TableConfig {
+            max_seats: MAX_SEATS_LIMIT,
+            ..TableConfig::default()
+        });
+        for &seat in seats {
+            game.take_seat(&format!("p{}", seat), seat).unwrap();
+        }
+        game
+    }
+
+    fn seat_of(game: &GameState, username: &str) -> usize {
+        game.players
+            .iter()
+            .find(|p| p.username == username)
+            .unwrap()
+            .seat
+    }
+
+    // このハンドでブラインドを払った席
+    fn posted_blinds(game: &GameState) -> (Option<usize>, Option<usize>) {
+        let mut small_blind = None;
+        let mut big_blind = None;
+        for event in &game.hand_history.events {
+            match event {
+                HandEvent::PostSmallBlind { username, .. } => {
+                    small_blind = Some(seat_of(game, username))
+                }
+                HandEvent::PostBigBlind { username, .. } => {
+                    big_blind = Some(seat_of(game, username))
+                }
+                _ => {}
+            }
+        }
+        (small_blind, big_blind)
+    }
+
+    // 全員フォールドでハンドを終わらせ、待機状態に戻す
+    fn fold_around(game: &mut GameState) {
+        while game.status.is_betting() {
+            let username = game.current_turn_username.clone().unwrap();
+            game.handle_action(&username, PlayerAction::Fold).unwrap();
+        }
+        let username = game.players[0].username.clone();
+        game.handle_action(&username, PlayerAction::NextHand)
+            .unwrap();
+    }
+
+    // 指定した席の次に座っているプレイヤーの席
+    fn next_seat(seats: &[usize], seat: usize) -> usize {
+        seats
+            .iter()
+            .copied()
+            .find(|&s| s > seat)
+            .unwrap_or(seats[0])
+    }
+
+    #[test]
+    fn blinds_rotate_one_seat_per_hand_for_2_to_10_players() {
+        for player_count in 2..=MAX_SEATS_LIMIT {
+            // 席の間に空席を挟む
+            let seats: Vec<usize> = (0..player_count)
+                .map(|i| i * MAX_SEATS_LIMIT / player_count)
+                .collect();
+            assert_eq!(seats.len(), player_count);
+            let mut game = table(&seats);
+            let mut big_blinds_posted = [0; MAX_SEATS_LIMIT];
+            let mut previous: Option<(usize, usize, usize)> = None;
+
+            for _ in 0..player_count * 2 {
+                game.start_game().unwrap();
+                let button = game.button_seat.unwrap();
+                let small_blind = game.small_blind_seat.unwrap();
+                let big_blind = game.big_blind_seat.unwrap();
+                assert_eq!(posted_blinds(&game), (Some(small_blind), Some(big_blind)));
+                big_blinds_posted[big_blind] += 1;
+
+                let first_to_act = seat_of(&game, game.current_turn_username.as_deref().unwrap());
+                if player_count == 2 {
+                    // ヘッズアップはボタンがSBを払い、プリフロップで先にアクションする
+                    assert_eq!(small_blind, button);
+                    assert_eq!(big_blind, next_seat(&seats, button));
+                    assert_eq!(first_to_act, button);
+                } else {
+                    assert_eq!(small_blind, next_seat(&seats, button));
+                    assert_eq!(big_blind, next_seat(&seats, small_blind));
+                    assert_eq!(first_to_act, next_seat(&seats, big_blind));
+                }
+                if let Some((previous_button, _, previous_big_blind)) = previous {
+                    assert_eq!(big_blind, next_seat(&seats, previous_big_blind));
+                    assert_eq!(button, next_seat(&seats, previous_button));
+                }
+                previous = Some((button, small_blind, big_blind));
+                fold_around(&mut game);
+            }
+
+            // 全員がちょうど2回ずつBBを払う
+            for &seat in &seats {
+                assert_eq!(
+                    big_blinds_posted[seat], 2,
+                    "{} players, seat {}",
+                    player_count, seat
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn heads_up_big_blind_acts_first_after_the_flop() {
+        let mut game = table(&[2, 7]);
+        game.start_game().unwrap();
+        let button = format!("p{}", game.button_seat.unwrap());
+        let big_blind = format!("p{}", game.big_blind_seat.unwrap());
+        assert_eq!(game.current_turn_username.as_deref(), Some(button.as_str()));
+        game.handle_action(&button, PlayerAction::Raise { to: 60 })
+            .unwrap();
+        game.handle_action(&big_blind, PlayerAction::Call).unwrap();
+        assert_eq!(game.status(), GamePhase::Flop);
+        assert_eq!(
+            game.current_turn_username.as_deref(),
+            Some(big_blind.as_str())
+        );
+    }
+
+    #[test]
+    fn small_blind_and_button_go_dead_when_players_leave() {
+        let mut game = table(&[0, 1, 2, 3, 4]);
+        game.start_game().unwrap();
+        assert_eq!(
+            (game.button_seat, game.small_blind_seat, game.big_blind_seat),
+            (Some(0), Some(1), Some(2))
+        );
+        fold_around(&mut game);
+
+        // BBだった席が空くと、次のハンドはSBが空席になる（誰もSBを払わない）
+        game.handle_action("p2", PlayerAction::LeaveTable).unwrap();
+        game.start_game().unwrap();
+        assert_eq!(
+            (game.button_seat, game.small_blind_seat, game.big_blind_seat),
+            (Some(1), Some(2), Some(3))
+        );
+        assert_eq!(posted_blinds(&game), (None, Some(3)));
+        fold_around(&mut game);
+
+        // 続くハンドではボタンが空席になる
+        game.start_game().unwrap();
+        assert_eq!(
+            (game.button_seat, game.small_blind_seat, game.big_blind_seat),
+            (Some(2), Some(3), Some(4))
+        );
+        assert_eq!(posted_blinds(&game), (Some(3), Some(4)));
+        assert_eq!(game.hand_history.dealer, "");
+        // ボタンが空席でもアクションはBBの次から
+        assert_eq!(game.current_turn_username.as_deref(), Some("p0"));
+    }
+
+    #[test]
+    fn nobody_posts_the_big_blind_twice_when_going_heads_up() {
+        for leaving_seat in 0..3 {
+            let mut game = table(&[0, 1, 2]);
+            game.start_game().unwrap();
+            let previous_big_blind = game.big_blind_seat;
+            fold_around(&mut game);
+
+            let username = format!("p{}", leaving_seat);
+            game.handle_action(&username, PlayerAction::LeaveTable)
+                .unwrap();
+            game.start_game().unwrap();
+            assert_ne!(game.big_blind_seat, previous_big_blind);
+            assert_eq!(game.button_seat, game.small_blind_seat);
+            let (small_blind, big_blind) = posted_blinds(&game);
+            assert_eq!(small_blind, game.button_seat);
+            assert_eq!(big_blind, game.big_blind_seat);
+        }
+    }
+
+    #[test]
+    fn sitting_out_player_is_skipped_and_owes_missed_blinds() {
+        let mut game = table(&[0, 1, 2, 3]);
+        game.start_game().unwrap();
+        fold_around(&mut game);
+        game.start_game().unwrap();
+        assert_eq!(posted_blinds(&game), (Some(2), Some(3)));
+        fold_around(&mut game);
+
+        // BBを払った直後に休憩すると、次のハンドのSBは誰も払わない
+        game.handle_action("p3", PlayerAction::SitOut).unwrap();
+        game.start_game().unwrap();
+        assert_eq!(posted_blinds(&game), (None, Some(0)));
+        assert!(game.players[3].hand.is_empty());
+        assert!(game.players[3].missed_small_blind);
+        assert!(!game.players[3].missed_big_blind);
+        for expected in [(Some(0), Some(1)), (Some(1), Some(2))] {
+            fold_around(&mut game);
+            game.start_game().unwrap();
+            assert_eq!(posted_blinds(&game), expected);
         }
+        fold_around(&mut game);
+
+        // BBの順番も飛ばされる
+        game.start_game().unwrap();
+        assert_eq!(posted_blinds(&game), (Some(2), Some(0)));
+        assert!(game.players[3].missed_big_blind);
+        fold_around(&mut game);
+
+        // 戻るとSB（デッド）とBB（ライブ）を両方払う
+        game.handle_action("p3", PlayerAction::SitIn).unwrap();
+        game.start_game().unwrap();
+        assert_eq!(posted_blinds(&game), (Some(0), Some(1)));
+        assert!(
+            game.hand_history
+                .events
+                .contains(&HandEvent::PostMissedBlinds {
+                    username: "p3".to_string(),
+                    live: 20,
+                    dead: 10,
+                })
+        );
+        assert!(!game.players[3].missed_small_blind && !game.players[3].missed_big_blind);
+    }
+
+    #[test]
+    fn new_player_between_button_and_big_blind_takes_the_button() {
+        let mut game = table(&[0, 5]);
+        game.start_game().unwrap();
+        assert_eq!(
+            (game.button_seat, game.small_blind_seat, game.big_blind_seat),
+            (Some(0), Some(0), Some(5))
+        );
+        fold_around(&mut game);
+
+        // BBが0番席まで回るので、ボタンは0番席に残さずSBの直前の3番席にする
+        game.take_seat("p3", 3).unwrap();
+        game.start_game().unwrap();
+        assert_eq!(
+            (game.button_seat, game.small_blind_seat, game.big_blind_seat),
+            (Some(3), Some(5), Some(0))
+        );
+        assert_eq!(game.current_turn_username.as_deref(), Some("p3"));
+    }
+
+    #[test]
+    fn big_blind_gets_an_option_and_checks_go_around() {
+        let mut game = table(&[0, 1, 2]);
+        game.start_game().unwrap();
+        game.handle_action("p0", PlayerAction::Call).unwrap();
+        game.handle_action("p1", PlayerAction::Call).unwrap();
+        // ベット額は揃っているが、BBはまだアクションしていない
+        assert_eq!(game.status(), GamePhase::PreFlop);
+        assert_eq!(game.current_turn_username.as_deref(), Some("p2"));
+        game.handle_action("p2", PlayerAction::Check).unwrap();
+        assert_eq!(game.status(), GamePhase::Flop);
+
+        // 全員がチェックするまで次のストリートへ進まない
+        for username in ["p1", "p2"] {
+            game.handle_action(username, PlayerAction::Check).unwrap();
+            assert_eq!(game.status(), GamePhase::Flop);
+        }
+        game.handle_action("p0", PlayerAction::Check).unwrap();
+        assert_eq!(game.status(), GamePhase::Turn);
+    }
+
+    #[test]
+    fn raise_reopens_action_and_is_tracked_as_last_aggressor() {
+        let mut game = table(&[0, 1, 2]);
+        game.start_game().unwrap();
+        game.handle_action("p0", PlayerAction::Call).unwrap();
+        game.handle_action("p1", PlayerAction::Call).unwrap();
+        game.handle_action("p2", PlayerAction::Raise { to: 60 })
+            .unwrap();
+        assert_eq!(game.last_aggressor.as_deref(), Some("p2"));
+        game.handle_action("p0", PlayerAction::Call).unwrap();
+        assert_eq!(game.status(), GamePhase::PreFlop);
+        game.handle_action("p1", PlayerAction::Call).unwrap();
+        assert_eq!(game.status(), GamePhase::Flop);
+        assert_eq!(game.last_aggressor, None);
+    }
+
+    #[test]
+    fn config_rejects_tables_whose_total_chips_overflow_an_integer() {
+        let config = |starting_stack, max_seats| TableConfig {
+            starting_stack,
+            max_seats,
+            ..TableConfig::default()
+        };
+        assert!(config(i32::MAX as u32 / 9, 9).validate().is_ok());
+        assert!(config(i32::MAX as u32 / 9 + 1, 9).validate().is_err());
+        assert!(config(i32::MAX as u32, 3).validate().is_err());
+    }
+
+    #[test]
+    fn raises_must_be_at_least_the_last_full_raise() {
+        let mut game = table(&[0, 1, 2, 3]);
+        game.start_game().unwrap();
+        // BB20に対して60へのレイズ（幅40）
+        game.handle_action("p3", PlayerAction::Raise { to: 60 })
+            .unwrap();
+        assert_eq!(
+            game.handle_action("p0", PlayerAction::Raise { to: 90 }),
+            Err(GameError::InvalidRaise {
+                min: 100,
+                max: 1000
+            })
+        );
+        game.handle_action("p0", PlayerAction::Raise { to: 100 })
+            .unwrap();
+        assert_eq!(
+            game.legal_actions().unwrap().raise,
+            Some(BetRange {
+                min: 140,
+                max: 1000
+            })
+        );
+        game.handle_action("p1", PlayerAction::Fold).unwrap();
+        game.handle_action("p2", PlayerAction::Fold).unwrap();
+        game.handle_action("p3", PlayerAction::Call).unwrap();
+
+        // 新しいストリートの最低ベットはBB
+        assert_eq!(game.status(), GamePhase::Flop);
+        let username = game.current_turn_username.clone().unwrap();
+        assert_eq!(
+            game.handle_action(&username, PlayerAction::Bet { amount: 10 }),
+            Err(GameError::InvalidBet { min: 20, max: 900 })
+        );
+    }
+
+    #[test]
+    fn short_all_in_does_not_change_the_minimum_raise() {
+        let mut game = table(&[0, 1, 2]);
+        game.players[1].stack = 150;
+        game.start_game().unwrap();
+        game.handle_action("p0", PlayerAction::Raise { to: 100 })
+            .unwrap();
+        // 幅50のオールインは最低レイズ幅80に届かない
+        game.handle_action("p1", PlayerAction::AllIn).unwrap();
+        assert_eq!(game.min_raise, 80);
+        // まだアクションしていないプレイヤーはレイズでき、最低額は150＋80
+        assert_eq!(game.current_turn_username.as_deref(), Some("p2"));
+        assert_eq!(
+            game.legal_actions().unwrap().raise,
+            Some(BetRange {
+                min: 230,
+                max: 1000
+            })
+        );
+    }
+
+    #[test]
+    fn short_all_in_must_be_answered_without_reopening_raises() {
+        let mut game = table(&[0, 1, 2]);
+        game.players[1].stack = 130;
+        game.start_game().unwrap();
+        game.handle_action("p0", PlayerAction::Raise { to: 100 })
+            .unwrap();
+        // 最低レイズ幅に満たないオールイン
+        game.handle_action("p1", PlayerAction::AllIn).unwrap();
+        game.handle_action("p2", PlayerAction::Call).unwrap();
+        // p0はすでにアクションしているが、追加の30にはコールかフォールドが必要
+        assert_eq!(game.status(), GamePhase::PreFlop);
+        assert_eq!(game.current_turn_username.as_deref(), Some("p0"));
+        let legal = game.legal_actions().unwrap();
+        assert_eq!(legal.call_amount, Some(30));
+        assert!(legal.raise.is_none());
+        game.handle_action("p0", PlayerAction::Call).unwrap();
+        assert_eq!(game.status(), GamePhase::Flop);
+
+        // オールインのプレイヤーは手番を飛ばされる
+        assert_eq!(game.current_turn_username.as_deref(), Some("p2"));
+        game.handle_action("p2", PlayerAction::Check).unwrap();
+        assert_eq!(game.current_turn_username.as_deref(), Some("p0"));
+    }
+
+    #[test]
+    fn all_in_hands_run_out_with_a_delay_between_streets() {
+        let mut game = table(&[0, 1]);
+        game.start_game().unwrap();
+        game.handle_action("p0", PlayerAction::AllIn).unwrap();
+        game.handle_action("p1", PlayerAction::Call).unwrap();
+
+        // ベットは終わったが、ボードはまだ配られていない。手札は公開される
+        assert_eq!(game.status(), GamePhase::PreFlop);
+        assert_eq!(game.current_turn_username, None);
+        assert!(game.sanitized().players.iter().all(|p| p.hand.len() == 2));
+        let runout_at = game.runout_at.unwrap();
+        assert!(!game.advance_runout(runout_at - Duration::milliseconds(1)));
+
+        let mut now = runout_at;
+        for (phase, board) in [
+            (GamePhase::Flop, 3),
+            (GamePhase::Turn, 4),
+            (GamePhase::River, 5),
+        ] {
+            assert!(game.advance_runout(now));
+            assert_eq!(game.status(), phase);
+            assert_eq!(game.community_cards.len(), board);
+            assert_eq!(game.current_turn_username, None);
+            now = game.runout_at.unwrap();
+        }
+        assert!(game.advance_runout(now));
+        assert_eq!(game.status(), GamePhase::Showdown);
+        assert_eq!(game.runout_at, None);
+        assert_eq!(game.players.iter().map(|p| p.stack).sum::<u32>(), 2000);
+    }
+
+    fn turn_deadline(game: &GameState) -> DateTime<Utc> {
+        game.turn_timer.as_ref().unwrap().deadline
+    }
+
+    fn last_action(game: &GameState) -> Option<(String, PlayerAction)> {
+        game.hand_history.events.iter().rev().find_map(|e| match e {
+            HandEvent::PlayerAction {
+                username, action, ..
+            } => Some((username.clone(), *action)),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn expired_turns_check_when_possible_and_fold_otherwise() {
+        let mut game = table(&[0, 1, 2]);
+        game.start_game().unwrap();
+
+        // ビッグブラインドに対してコールが必要なので、時間切れはフォールド
+        let first = game.current_turn_username.clone().unwrap();
+        let deadline = turn_deadline(&game);
+        assert!(!game.handle_timeout(deadline - Duration::milliseconds(1)));
+        assert!(game.handle_timeout(deadline));
+        assert_eq!(last_action(&game), Some((first, PlayerAction::Fold)));
+
+        // BBのオプションはチェックできるので、時間切れはチェック
+        let small_blind = game.current_turn_username.clone().unwrap();
+        game.handle_action(&small_blind, PlayerAction::Call)
+            .unwrap();
+        let big_blind = game.current_turn_username.clone().unwrap();
+        assert!(game.handle_timeout(turn_deadline(&game)));
+        assert_eq!(last_action(&game), Some((big_blind, PlayerAction::Check)));
+        assert_eq!(game.status(), GamePhase::Flop);
+    }
+
+    #[test]
+    fn turn_deadline_is_reset_after_each_action() {
+        let mut game = table(&[0, 1, 2]);
+        game.start_game().unwrap();
+        let timeout = Duration::seconds(game.config.action_timeout_secs as i64);
+
+        for _ in 0..2 {
+            let username = game.current_turn_username.clone().unwrap();
+            let before = Utc::now();
+            game.handle_action(&username, PlayerAction::Call).unwrap();
+            let timer = game.turn_timer.as_ref().unwrap();
+            assert_eq!(Some(&timer.username), game.current_turn_username.as_ref());
+            assert_ne!(timer.username, username);
+            assert!(!timer.using_time_bank);
+            assert!(timer.deadline >= before + timeout);
+            assert!(timer.deadline <= Utc::now() + timeout);
+        }
+    }
+
+    #[test]
+    fn time_bank_is_used_after_the_turn_timer_and_not_refilled_during_the_hand() {
+        let mut game = GameState::new(TableConfig {
+            time_bank_secs: 120,
+            ..TableConfig::default()
+        });
+        for seat in 0..2 {
+            game.take_seat(&format!("p{}", seat), seat).unwrap();
+        }
+        game.start_game().unwrap();
+        let username = game.current_turn_username.clone().unwrap();
+
+        // 100秒前に持ち時間が切れ、タイムバンクに入った
+        let expired_at = Utc::now() - Duration::seconds(100);
+        game.turn_timer.as_mut().unwrap().deadline = expired_at;
+        assert!(game.handle_timeout(expired_at));
+        let timer = game.turn_timer.as_ref().unwrap();
+        assert!(timer.using_time_bank);
+        assert_eq!(timer.deadline, expired_at + Duration::seconds(120));
+
+        // タイムバンク中にアクションすると、使った100秒が差し引かれる
+        game.handle_action(&username, PlayerAction::Call).unwrap();
+        let time_bank = |game: &GameState| {
+            game.players
+                .iter()
+                .find(|p| p.username == username)
+                .unwrap()
+                .time_bank_secs
+        };
+        assert_eq!(time_bank(&game), 20);
+
+        // 同じハンドの次の手番（フロップ）でも補充されず、使い切るとチェックされて0になる
+        let other = game.current_turn_username.clone().unwrap();
+        game.handle_action(&other, PlayerAction::Check).unwrap();
+        game.handle_action(&other, PlayerAction::Check).unwrap();
+        assert_eq!(
+            game.current_turn_username.as_deref(),
+            Some(username.as_str())
+        );
+        let deadline = turn_deadline(&game);
+        assert!(game.handle_timeout(deadline));
+        assert_eq!(turn_deadline(&game), deadline + Duration::seconds(20));
+        assert!(game.handle_timeout(deadline + Duration::seconds(20)));
+        assert_eq!(
+            last_action(&game),
+            Some((username.clone(), PlayerAction::Check))
+        );
+        assert_eq!(time_bank(&game), 0);
+    }
+
+    #[test]
+    fn disconnected_players_keep_their_seat_within_the_grace_period() {
+        let mut game = table(&[0, 1, 2]);
+        let grace = Duration::seconds(RECONNECT_GRACE_SECS);
+        let disconnected_at = Utc::now();
+        game.mark_disconnected("p1", disconnected_at);
+
+        assert!(!game.remove_expired_players(disconnected_at + grace - Duration::seconds(1)));
+        assert_eq!(game.players.len(), 3);
+        assert!(game.remove_expired_players(disconnected_at + grace));
+        let seated: Vec<&str> = game.players.iter().map(|p| p.username.as_str()).collect();
+        assert_eq!(seated, vec!["p0", "p2"]);
+        assert_eq!(game.take_cash_outs()[0].username, "p1");
+    }
+
+    #[test]
+    fn expired_players_are_only_removed_between_hands() {
+        let mut game = table(&[0, 1, 2]);
+        let disconnected_at = Utc::now();
+        game.start_game().unwrap();
+        game.mark_disconnected("p1", disconnected_at);
+
+        let expired = disconnected_at + Duration::seconds(RECONNECT_GRACE_SECS);
+        assert!(!game.remove_expired_players(expired));
+        fold_around(&mut game);
+        assert!(game.remove_expired_players(expired));
+        assert_eq!(game.players.len(), 2);
+    }
+
+    #[test]
+    fn disconnected_player_is_acted_for_without_waiting_for_the_timer() {
+        let mut game = table(&[0, 1, 2]);
+        game.start_game().unwrap();
+        let username = game.current_turn_username.clone().unwrap();
+        let now = Utc::now();
+        game.mark_disconnected(&username, now);
+
+        assert!(now < turn_deadline(&game));
+        assert!(game.handle_timeout(now));
+        assert_eq!(last_action(&game), Some((username, PlayerAction::Fold)));
+    }
+
+    #[test]
+    fn reconnecting_clears_the_disconnection() {
+        let mut game = table(&[0, 1]);
+        let disconnected_at = Utc::now();
+        game.mark_disconnected("p0", disconnected_at);
+        assert!(game.reconnect("p0"));
+        assert_eq!(game.players[0].disconnected_at, None);
+        assert!(!game.reconnect("p9"));
+
+        let expired = disconnected_at + Duration::seconds(RECONNECT_GRACE_SECS);
+        assert!(!game.remove_expired_players(expired));
+        assert_eq!(game.players.len(), 2);
+    }
+
+    #[test]
+    fn room_is_abandoned_once_every_player_is_past_the_grace_period() {
+        let mut game = table(&[0, 1]);
+        let grace = Duration::seconds(RECONNECT_GRACE_SECS);
+        let disconnected_at = Utc::now();
+        game.mark_disconnected("p0", disconnected_at);
+        assert!(!game.is_abandoned(disconnected_at + grace));
+
+        game.mark_disconnected("p1", disconnected_at + Duration::seconds(10));
+        assert!(!game.is_abandoned(disconnected_at + grace));
+        assert!(game.is_abandoned(disconnected_at + grace + Duration::seconds(10)));
+    }
+
+    // 配る順にカードを並べたデッキ（席順に2枚ずつ、続いてボード）
+    fn preset_deck(hands: &[[(Rank, Suit); 2]], board: &[(Rank, Suit)]) -> Deck {
+        let cards: Vec<Card> = hands
+            .iter()
+            .flatten()
+            .chain(board)
+            .map(|&(rank, suit)| Card { rank, suit })
+            .collect();
+        Deck::stacked(&cards)
+    }
+
+    // 全員チェックでショーダウンまで進める
+    fn check_down(game: &mut GameState) {
+        while game.status.is_betting() {
+            let username = game.current_turn_username.clone().unwrap();
+            game.handle_action(&username, PlayerAction::Check).unwrap();
+        }
+    }
+
+    #[test]
+    fn odd_chip_goes_to_the_first_winner_left_of_the_button() {
+        let mut game = table(&[0, 1, 2]);
+        game.config.small_blind = 5;
+        game.config.big_blind = 10;
+        // ボードのブロードウェイストレートで引き分けになる
+        game.preset_next_deck(preset_deck(
+            &[
+                [(Rank::Two, Suit::Club), (Rank::Three, Suit::Diamond)],
+                [(Rank::Four, Suit::Club), (Rank::Five, Suit::Diamond)],
+                [(Rank::Two, Suit::Diamond), (Rank::Three, Suit::Club)],
+            ],
+            &[
+                (Rank::Ace, Suit::Spade),
+                (Rank::King, Suit::Club),
+                (Rank::Queen, Suit::Diamond),
+                (Rank::Jack, Suit::Heart),
+                (Rank::Ten, Suit::Spade),
+            ],
+        ));
+        game.start_game().unwrap();
+
+        game.handle_action("p0", PlayerAction::Call).unwrap();
+        game.handle_action("p1", PlayerAction::Fold).unwrap();
+        game.handle_action("p2", PlayerAction::Check).unwrap();
+        check_down(&mut game);
+
+        // ポット25を分け、端数の1枚はボタン（0番席）の左にいる2番席へ
+        assert_eq!(game.status(), GamePhase::Showdown);
+        assert_eq!(game.pot, 0);
+        let stacks: Vec<u32> = game.players.iter().map(|p| p.stack).collect();
+        assert_eq!(stacks, vec![1002, 995, 1003]);
+    }
+
+    #[test]
+    fn side_pots_are_split_separately() {
+        let mut game = table(&[0, 1, 2]);
+        game.players[1].stack = 55;
+        // ショートスタックのストレートがメインポットを取り、残る2人はサイドポットを分ける
+        game.preset_next_deck(preset_deck(
+            &[
+                [(Rank::Ace, Suit::Diamond), (Rank::Three, Suit::Club)],
+                [(Rank::Ten, Suit::Spade), (Rank::Four, Suit::Club)],
+                [(Rank::Ace, Suit::Heart), (Rank::Three, Suit::Diamond)],
+            ],
+            &[
+                (Rank::Ace, Suit::Spade),
+                (Rank::King, Suit::Club),
+                (Rank::Queen, Suit::Diamond),
+                (Rank::Jack, Suit::Heart),
+                (Rank::Two, Suit::Spade),
+            ],
+        ));
+        game.start_game().unwrap();
+
+        game.handle_action("p0", PlayerAction::Raise { to: 101 })
+            .unwrap();
+        game.handle_action("p1", PlayerAction::AllIn).unwrap();
+        game.handle_action("p2", PlayerAction::Call).unwrap();
+        check_down(&mut game);
+
+        assert_eq!(game.status(), GamePhase::Showdown);
+        assert_eq!(game.pot, 0);
+        let stacks: Vec<u32> = game.players.iter().map(|p| p.stack).collect();
+        assert_eq!(stacks, vec![945, 165, 945]);
+        assert_eq!(game.completed_hands.len(), 1);
+    }
+
+    #[test]
+    fn pots_are_built_at_each_all_in_level_and_include_folded_chips() {
+        let mut game = table(&[0, 1, 2, 3]);
+        // p0は100でオールイン、p1とp2は300ずつ、p3は400出してからフォールド
+        for (player, total_bet, is_active) in [
+            (0, 100, true),
+            (1, 300, true),
+            (2, 300, true),
+            (3, 400, false),
+        ] {
+            game.players[player].total_bet = total_bet;
+            game.players[player].is_active = is_active;
+        }
+        let eligible = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        assert_eq!(
+            game.build_pots(),
+            vec![
+                Pot {
+                    amount: 400,
+                    eligible_players: eligible(&["p0", "p1", "p2"]),
+                },
+                // フォールドしたp3が最大レベルを超えて出した100も含む
+                Pot {
+                    amount: 700,
+                    eligible_players: eligible(&["p1", "p2"]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn uncalled_all_in_is_returned_instead_of_forming_a_side_pot() {
+        let mut game = table(&[0, 1]);
+        game.players[1].stack = 300;
+        game.preset_next_deck(preset_deck(
+            &[
+                [(Rank::Seven, Suit::Diamond), (Rank::Two, Suit::Club)],
+                [(Rank::Ace, Suit::Spade), (Rank::Ace, Suit::Heart)],
+            ],
+            &[
+                (Rank::King, Suit::Club),
+                (Rank::Queen, Suit::Diamond),
+                (Rank::Nine, Suit::Heart),
+                (Rank::Four, Suit::Spade),
+                (Rank::Three, Suit::Club),
+            ],
+        ));
+        game.start_game().unwrap();
+        game.handle_action("p0", PlayerAction::AllIn).unwrap();
+        game.handle_action("p1", PlayerAction::Call).unwrap();
+
+        // p1がコールできなかった700はすぐにp0に返す
+        assert_eq!(game.players[0].stack, 700);
+        assert_eq!(game.pot, 600);
+        let mut now = game.runout_at.unwrap();
+        while game.advance_runout(now) {
+            now = game.runout_at.unwrap_or(now);
+        }
+        assert_eq!(game.status(), GamePhase::Showdown);
+        let stacks: Vec<u32> = game.players.iter().map(|p| p.stack).collect();
+        assert_eq!(stacks, vec![700, 600]);
+        assert_eq!(
+            game.winner_message.as_deref(),
+            Some("p1がワンペアでメインポット600を獲得しました。")
+        );
+
+        let result = &game.completed_hands[0];
+        assert_eq!(result.pot, 600);
+        let winners: Vec<bool> = result.players.iter().map(|p| p.is_winner).collect();
+        assert_eq!(winners, vec![false, true]);
+        let events = &result.history.events;
+        assert!(events.contains(&HandEvent::ReturnUncalledBet {
+            username: "p0".to_string(),
+            amount: 700,
+        }));
+        let collected: Vec<&HandEvent> = events
+            .iter()
+            .filter(|e| matches!(e, HandEvent::CollectPot { .. }))
+            .collect();
+        assert_eq!(
+            collected,
+            vec![&HandEvent::CollectPot {
+                username: "p1".to_string(),
+                amount: 600,
+                pot_index: 0,
+            }]
+        );
+        // オールインでランアウトしたハンドなので、両者の手札はショーダウンで公開される
+        let shown: Vec<String> = showdown_events(&game)
+            .into_iter()
+            .filter_map(|e| match e {
+                HandEvent::Showdown { username, .. } => Some(username),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(shown, vec!["p1", "p0"]);
+
+        let p0_stats = StatsCounter::for_hand("p0", &result.history, -300);
+        assert_eq!(
+            (p0_stats.went_to_showdown, p0_stats.won_at_showdown),
+            (1, 0)
+        );
+        let p1_stats = StatsCounter::for_hand("p1", &result.history, 300);
+        assert_eq!(
+            (p1_stats.went_to_showdown, p1_stats.won_at_showdown),
+            (1, 1)
+        );
+    }
+
+    // p1がフォールドし、p0（指定した手札）とp2（K 9）がリバーまで進んだテーブル
+    fn table_at_the_river(p0_hand: [(Rank, Suit); 2]) -> GameState {
+        let mut game = table(&[0, 1, 2]);
+        game.preset_next_deck(preset_deck(
+            &[
+                p0_hand,
+                [(Rank::Queen, Suit::Club), (Rank::Jack, Suit::Club)],
+                [(Rank::King, Suit::Diamond), (Rank::Nine, Suit::Club)],
+            ],
+            &[
+                (Rank::Ace, Suit::Spade),
+                (Rank::King, Suit::Club),
+                (Rank::Seven, Suit::Diamond),
+                (Rank::Four, Suit::Heart),
+                (Rank::Two, Suit::Spade),
+            ],
+        ));
+        game.start_game().unwrap();
+        game.handle_action("p0", PlayerAction::Call).unwrap();
+        game.handle_action("p1", PlayerAction::Fold).unwrap();
+        game.handle_action("p2", PlayerAction::Check).unwrap();
+        while game.status() != GamePhase::River {
+            let username = game.current_turn_username.clone().unwrap();
+            game.handle_action(&username, PlayerAction::Check).unwrap();
+        }
+        game
+    }
+
+    fn showdown_events(game: &GameState) -> Vec<HandEvent> {
+        game.completed_hands[0]
+            .history
+            .events
+            .iter()
+            .filter(|e| matches!(e, HandEvent::Showdown { .. } | HandEvent::MuckCards { .. }))
+            .cloned()
+            .collect()
+    }
+
+    fn revealed_hands(game: &GameState) -> Vec<usize> {
+        game.sanitized()
+            .players
+            .iter()
+            .map(|p| p.hand.len())
+            .collect()
+    }
+
+    #[test]
+    fn last_aggressor_shows_first_and_losers_may_muck() {
+        let mut game = table_at_the_river([(Rank::Ace, Suit::Diamond), (Rank::Ace, Suit::Heart)]);
+        game.handle_action("p2", PlayerAction::Check).unwrap();
+        game.handle_action("p0", PlayerAction::Bet { amount: 40 })
+            .unwrap();
+        game.handle_action("p2", PlayerAction::Call).unwrap();
+        assert_eq!(game.status(), GamePhase::Showdown);
+
+        // フォールドしたp1とマックしたp2の手札は見えない
+        assert_eq!(revealed_hands(&game), vec![2, 0, 0]);
+        let events = showdown_events(&game);
+        assert!(matches!(&events[0], HandEvent::Showdown { username, .. } if username == "p0"));
+        assert_eq!(
+            events[1],
+            HandEvent::MuckCards {
+                username: "p2".to_string()
+            }
+        );
+        assert_eq!(game.completed_hands[0].players[2].hand_rank, None);
+    }
+
+    #[test]
+    fn better_hands_are_shown_after_the_last_aggressor() {
+        let mut game = table_at_the_river([(Rank::Ace, Suit::Diamond), (Rank::Ace, Suit::Heart)]);
+        game.handle_action("p2", PlayerAction::Bet { amount: 40 })
+            .unwrap();
+        game.handle_action("p0", PlayerAction::Call).unwrap();
+
+        assert_eq!(revealed_hands(&game), vec![2, 0, 2]);
+        let events = showdown_events(&game);
+        assert!(matches!(&events[0], HandEvent::Showdown { username, .. } if username == "p2"));
+        assert!(matches!(&events[1], HandEvent::Showdown { username, .. } if username == "p0"));
+    }
+
+    #[test]
+    fn without_a_bet_the_first_player_left_of_the_button_shows_first() {
+        let mut game = table_at_the_river([(Rank::Three, Suit::Diamond), (Rank::Six, Suit::Heart)]);
+        check_down(&mut game);
+
+        // p2（ボタンの左）が先に見せ、負けているp0はマックする
+        assert_eq!(revealed_hands(&game), vec![0, 0, 2]);
+        let events = showdown_events(&game);
+        assert!(matches!(&events[0], HandEvent::Showdown { username, .. } if username == "p2"));
+        assert!(matches!(&events[1], HandEvent::MuckCards { username } if username == "p0"));
+    }
+
+    #[test]
+    fn winner_may_show_cards_after_winning_uncontested() {
+        let mut game = table(&[0, 1, 2]);
+        game.start_game().unwrap();
+        assert_eq!(
+            game.handle_action("p0", PlayerAction::ShowCards),
+            Err(GameError::HandNotFinished)
+        );
+        game.handle_action("p0", PlayerAction::Raise { to: 60 })
+            .unwrap();
+        game.handle_action("p1", PlayerAction::Fold).unwrap();
+        game.handle_action("p2", PlayerAction::Fold).unwrap();
+        assert_eq!(game.status(), GamePhase::HandComplete);
+        assert_eq!(revealed_hands(&game), vec![0, 0, 0]);
+
+        assert_eq!(
+            game.handle_action("p1", PlayerAction::ShowCards),
+            Err(GameError::NoCardsToShow)
+        );
+        game.handle_action("p0", PlayerAction::ShowCards).unwrap();
+        assert_eq!(revealed_hands(&game), vec![2, 0, 0]);
+    }
+
+    #[test]
+    fn server_seed_is_committed_before_the_deal_and_revealed_after_the_hand() {
+        let mut game = table(&[0, 1, 2]);
+        game.set_client_seed("p1", "lucky".to_string()).unwrap();
+        let commitment = game.seed_commitment.clone();
+        game.start_game().unwrap();
+        let hands: Vec<Vec<Card>> = game.players.iter().map(|p| p.hand.clone()).collect();
+
+        // ハンド中はハッシュとプレイヤーのシードだけが見え、次のハンドのハッシュは別になる
+        let proof = game.sanitized().shuffle_proof.unwrap();
+        assert_eq!(proof.server_seed_hash, commitment);
+        assert_eq!(proof.server_seed, None);
+        assert_eq!(
+            proof.client_seeds,
+            vec![ClientSeed {
+                username: "p1".to_string(),
+                seed: "lucky".to_string(),
+            }]
+        );
+        assert_ne!(game.seed_commitment, commitment);
+        assert_eq!(
+            fairness::verify(&proof).err(),
+            Some(fairness::VerifyError::ServerSeedNotRevealed)
+        );
+
+        // ハンド後に公開されたシードから、配られた手札を作り直せる（観戦者を含め全員に送る）
+        fold_around(&mut game);
+        assert!(
+            game.sanitized()
+                .shuffle_proof
+                .unwrap()
+                .server_seed
+                .is_some()
+        );
+        let history = game.take_completed_hands().remove(0).history;
+        let proof = history.shuffle.unwrap();
+        assert_eq!(game.shuffle_proof.as_ref(), Some(&proof));
+        let mut deck = fairness::verify(&proof).unwrap();
+        for hand in hands {
+            assert_eq!(hand, vec![deck.deal(), deck.deal()]);
+        }
+
+        // サーバーシードかプレイヤーのシードを差し替えると、ハッシュが合わないかデッキが変わる
+        let mut tampered = proof.clone();
+        tampered.server_seed = Some(hex::encode([0u8; 32]));
+        assert_eq!(
+            fairness::verify(&tampered).err(),
+            Some(fairness::VerifyError::HashMismatch)
+        );
+        let mut tampered = proof.clone();
+        tampered.client_seeds[0].seed = "unlucky".to_string();
+        assert_ne!(
+            fairness::verify(&tampered).unwrap().dealing_order(),
+            fairness::verify(&proof).unwrap().dealing_order()
+        );
+    }
+
+    #[test]
+    fn only_seated_players_can_send_a_bounded_client_seed() {
+        let mut game = table(&[0, 1]);
+        assert_eq!(
+            game.set_client_seed("nobody", "x".to_string()),
+            Err(GameError::PlayerNotFound)
+        );
+        assert_eq!(
+            game.set_client_seed("p0", String::new()),
+            Err(GameError::InvalidClientSeed)
+        );
+        assert_eq!(
+            game.set_client_seed("p0", "x".repeat(MAX_CLIENT_SEED_LEN + 1)),
+            Err(GameError::InvalidClientSeed)
+        );
+        game.set_client_seed("p0", "x".repeat(MAX_CLIENT_SEED_LEN))
+            .unwrap();
+
+        // 席を立つとシードも外れる
+        game.handle_action("p0", PlayerAction::LeaveTable).unwrap();
+        assert!(game.client_seeds.is_empty());
+    }
+
+    #[test]
+    fn same_seed_deals_the_same_hands_and_the_recorded_seed_reproduces_the_deck() {
+        let deal = |seed: u64| {
+            let mut game = GameState::with_seed(TableConfig::default(), seed);
+            for (seat, username) in ["a", "b", "c"].into_iter().enumerate() {
+                game.take_seat(username, seat).unwrap();
+            }
+            game.start_game().unwrap();
+            game
+        };
+        let game = deal(42);
+        let hands: Vec<Vec<Card>> = game.players.iter().map(|p| p.hand.clone()).collect();
+        assert_eq!(
+            deal(42)
+                .players
+                .iter()
+                .map(|p| p.hand.clone())
+                .collect::<Vec<_>>(),
+            hands
+        );
+        assert_ne!(
+            deal(43)
+                .players
+                .iter()
+                .map(|p| p.hand.clone())
+                .collect::<Vec<_>>(),
+            hands
+        );
+
+        // 記録されたシードからデッキを作り直すと、同じ手札が配られる
+        let seed: [u8; 32] = hex::decode(game.hand_history.seed.as_ref().unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let mut deck = Deck::shuffled(seed);
+        for hand in hands {
+            assert_eq!(hand, vec![deck.deal(), deck.deal()]);
+        }
+    }
+
+    #[test]
+    fn tournament_continues_below_the_minimum_players_after_a_bust_out() {
+        let mut game = GameState::new(TableConfig {
+            max_seats: 3,
+            min_players: 3,
+            tournament: Some(TournamentConfig {
+                levels: vec![BlindLevel {
+                    small_blind: 10,
+                    big_blind: 20,
+                    ante: 0,
+                }],
+                advance: LevelAdvance::Hands { hands_per_level: 5 },
+            }),
+            ..TableConfig::default()
+        });
+        for seat in 0..3 {
+            game.take_seat(&format!("p{}", seat), seat).unwrap();
+        }
+        game.players[0].stack = 100;
+        game.preset_next_deck(preset_deck(
+            &[
+                [(Rank::Seven, Suit::Diamond), (Rank::Two, Suit::Club)],
+                [(Rank::Ace, Suit::Spade), (Rank::Ace, Suit::Heart)],
+                [(Rank::King, Suit::Spade), (Rank::King, Suit::Heart)],
+            ],
+            &[
+                (Rank::Queen, Suit::Club),
+                (Rank::Jack, Suit::Diamond),
+                (Rank::Nine, Suit::Heart),
+                (Rank::Four, Suit::Spade),
+                (Rank::Three, Suit::Club),
+            ],
+        ));
+        game.start_game().unwrap();
+
+        // p0がオールインし、残る2人はコールしてチェックで進める
+        while game.status.is_betting() {
+            let username = game.current_turn_username.clone().unwrap();
+            let action = if username == "p0" {
+                PlayerAction::AllIn
+            } else if game.legal_actions().unwrap().call_amount.is_some() {
+                PlayerAction::Call
+            } else {
+                PlayerAction::Check
+            };
+            game.handle_action(&username, action).unwrap();
+        }
+        let tournament = game.tournament.as_ref().unwrap();
+        assert_eq!(tournament.finishing_places[0].username, "p0");
+        assert_eq!(tournament.finishing_places[0].place, 3);
+
+        // 最低人数の3人を下回っても、次のハンドを配れる
+        game.handle_action("p1", PlayerAction::NextHand).unwrap();
+        game.start_game().unwrap();
+        assert_eq!(game.status(), GamePhase::PreFlop);
+        assert_eq!(game.hand_history.seats.len(), 2);
+    }
+
+    #[test]
+    fn busted_players_are_skipped() {
+        let mut game = table(&[0, 1, 2, 3]);
+        game.players[2].stack = 0;
+        game.start_game().unwrap();
+        assert_eq!(
+            (game.button_seat, game.small_blind_seat, game.big_blind_seat),
+            (Some(0), Some(1), Some(3))
+        );
+        fold_around(&mut game);
+        game.start_game().unwrap();
+        assert_eq!(
+            (game.button_seat, game.small_blind_seat, game.big_blind_seat),
+            (Some(1), Some(3), Some(0))
+        );
+        assert!(game.players[2].hand.is_empty());
     }
-    deck
 }
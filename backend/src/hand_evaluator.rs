@@ -1,6 +1,8 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::cmp::Ordering;
-use std::collections::HashMap;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Suit {
@@ -10,6 +12,29 @@ pub enum Suit {
     Spade,
 }
 
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Heart, Suit::Diamond, Suit::Club, Suit::Spade];
+
+    fn to_char(self) -> char {
+        match self {
+            Suit::Heart => 'H',
+            Suit::Diamond => 'D',
+            Suit::Club => 'C',
+            Suit::Spade => 'S',
+        }
+    }
+
+    fn from_char(c: char) -> Option<Suit> {
+        match c {
+            'H' => Some(Suit::Heart),
+            'D' => Some(Suit::Diamond),
+            'C' => Some(Suit::Club),
+            'S' => Some(Suit::Spade),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Rank {
     Two,
@@ -27,12 +52,97 @@ pub enum Rank {
     Ace,
 }
 
+impl Rank {
+    pub const ALL: [Rank; 13] = [
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+        Rank::Ace,
+    ];
+
+    fn to_char(self) -> char {
+        match self {
+            Rank::Two => '2',
+            Rank::Three => '3',
+            Rank::Four => '4',
+            Rank::Five => '5',
+            Rank::Six => '6',
+            Rank::Seven => '7',
+            Rank::Eight => '8',
+            Rank::Nine => '9',
+            Rank::Ten => 'T',
+            Rank::Jack => 'J',
+            Rank::Queen => 'Q',
+            Rank::King => 'K',
+            Rank::Ace => 'A',
+        }
+    }
+
+    fn from_char(c: char) -> Option<Rank> {
+        Rank::ALL.into_iter().find(|r| r.to_char() == c)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Card {
     pub rank: Rank,
     pub suit: Suit,
 }
 
+// "AS", "TD" のような2文字表記（ランク＋スート）
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.rank.to_char(), self.suit.to_char())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCardError(String);
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid card: {:?}", self.0)
+    }
+}
+
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let (Some(rank), Some(suit), None) = (chars.next(), chars.next(), chars.next()) else {
+            return Err(ParseCardError(s.to_string()));
+        };
+        match (Rank::from_char(rank), Suit::from_char(suit)) {
+            (Some(rank), Some(suit)) => Ok(Card { rank, suit }),
+            _ => Err(ParseCardError(s.to_string())),
+        }
+    }
+}
+
+// クライアントとは文字列表記でやり取りする
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 // Ordを手動実装してカードの強さを比較できるようにする
 impl PartialOrd for Card {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
@@ -76,40 +186,197 @@ impl fmt::Display for HandRank {
     }
 }
 
-// カードの文字列("As", "Td", "7c")をCard構造体に変換
-pub fn parse_cards(card_strs: &[String]) -> Vec<Card> {
-    card_strs.iter().filter_map(|s| parse_card(s)).collect()
+// 役の強さを比較できる値。大きいほど強い。
+// 上位4ビットが役の種類、残りの4ビットずつが比べる順のランク（HandRankのフィールドの順）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HandStrength(u32);
+
+// 役の種類（HandStrengthの上位4ビット）
+const HIGH_CARD: u32 = 0;
+const ONE_PAIR: u32 = 1;
+const TWO_PAIR: u32 = 2;
+const THREE_OF_A_KIND: u32 = 3;
+const STRAIGHT: u32 = 4;
+const FLUSH: u32 = 5;
+const FULL_HOUSE: u32 = 6;
+const FOUR_OF_A_KIND: u32 = 7;
+const STRAIGHT_FLUSH: u32 = 8;
+
+impl HandStrength {
+    fn new(category: u32, ranks: &[usize]) -> Self {
+        let mut value = category;
+        for i in 0..5 {
+            value = value << 4 | ranks.get(i).map_or(0, |&rank| rank as u32);
+        }
+        HandStrength(value)
+    }
+
+    // 役の名前やキッカーを表すHandRankに戻す
+    pub fn hand_rank(self) -> HandRank {
+        let r = |i: u32| Rank::ALL[(self.0 >> (16 - 4 * i) & 0xF) as usize];
+        match self.0 >> 20 {
+            HIGH_CARD => HandRank::HighCard(r(0), r(1), r(2), r(3), r(4)),
+            ONE_PAIR => HandRank::OnePair(r(0), r(1), r(2), r(3)),
+            TWO_PAIR => HandRank::TwoPair(r(0), r(1), r(2)),
+            THREE_OF_A_KIND => HandRank::ThreeOfAKind(r(0), r(1), r(2)),
+            STRAIGHT => HandRank::Straight(r(0)),
+            FLUSH => HandRank::Flush(r(0), r(1), r(2), r(3), r(4)),
+            FULL_HOUSE => HandRank::FullHouse(r(0), r(1)),
+            FOUR_OF_A_KIND => HandRank::FourOfAKind(r(0), r(1)),
+            _ if r(0) == Rank::Ace => HandRank::RoyalFlush,
+            _ => HandRank::StraightFlush(r(0)),
+        }
+    }
 }
 
-fn parse_card(s: &str) -> Option<Card> {
-    if s.len() != 2 {
-        return None;
+// 二項係数 C(n, k)（n < 20, k < 8）。ランクの組み合わせを表の番号に変換するのに使う
+const BINOMIAL: [[u32; 8]; 20] = {
+    let mut table = [[0; 8]; 20];
+    let mut n = 0;
+    while n < 20 {
+        table[n][0] = 1;
+        let mut k = 1;
+        while k < 8 && k <= n {
+            table[n][k] = table[n - 1][k - 1] + table[n - 1][k];
+            k += 1;
+        }
+        n += 1;
     }
-    let mut chars = s.chars();
-    let rank = match chars.next()? {
-        '2' => Rank::Two,
-        '3' => Rank::Three,
-        '4' => Rank::Four,
-        '5' => Rank::Five,
-        '6' => Rank::Six,
-        '7' => Rank::Seven,
-        '8' => Rank::Eight,
-        '9' => Rank::Nine,
-        'T' => Rank::Ten,
-        'J' => Rank::Jack,
-        'Q' => Rank::Queen,
-        'K' => Rank::King,
-        'A' => Rank::Ace,
-        _ => return None,
-    };
-    let suit = match chars.next()? {
-        'H' => Suit::Heart,
-        'D' => Suit::Diamond,
-        'C' => Suit::Club,
-        'S' => Suit::Spade,
-        _ => return None,
+    table
+};
+
+// 役の強さの表。起動後最初の判定で一度だけ作る
+struct Tables {
+    // 同じスートのカードのランクの集合（13ビット）→ フラッシュ・ストレートフラッシュの強さ
+    flush: Vec<HandStrength>,
+    // 5〜7枚のランクの組み合わせ（スートは無視）→ フラッシュ以外の役の強さ。枚数ごとに表を分ける
+    ranks: [Vec<HandStrength>; 3],
+}
+
+static TABLES: LazyLock<Tables> = LazyLock::new(Tables::build);
+
+impl Tables {
+    fn build() -> Self {
+        let flush = (0..1usize << 13)
+            .map(|mask| {
+                let mask = mask as u16;
+                if mask.count_ones() < 5 {
+                    return HandStrength(0); // フラッシュにならない（引かれることはない）
+                }
+                match straight_high(mask) {
+                    Some(high) => HandStrength::new(STRAIGHT_FLUSH, &[high]),
+                    None => {
+                        let ranks: Vec<usize> =
+                            (0..13).rev().filter(|&r| mask & 1 << r != 0).collect();
+                        HandStrength::new(FLUSH, &ranks)
+                    }
+                }
+            })
+            .collect();
+        let ranks = [5, 6, 7].map(|cards| {
+            let mut table = vec![HandStrength(0); BINOMIAL[12 + cards][cards] as usize];
+            fill_rank_table(&mut table, &mut [0; 13], 0, cards);
+            table
+        });
+        Tables { flush, ranks }
+    }
+}
+
+// 枚数がcardsになるランクの組み合わせ（同じランクは4枚まで）をすべて列挙して表を埋める
+fn fill_rank_table(table: &mut [HandStrength], counts: &mut [u8; 13], rank: usize, cards: usize) {
+    if rank == 13 {
+        if cards == 0 {
+            table[rank_index(counts)] = strength_without_flush(counts);
+        }
+        return;
+    }
+    for count in 0..=cards.min(4) {
+        counts[rank] = count as u8;
+        fill_rank_table(table, counts, rank + 1, cards - count);
+    }
+    counts[rank] = 0;
+}
+
+// ランクの組み合わせ（重複あり）に、枚数ごとに0から隙間なく番号を振る（組み合わせ数え上げによる完全ハッシュ）
+fn rank_index(counts: &[u8; 13]) -> usize {
+    let mut index = 0;
+    let mut i = 0;
+    for (rank, &count) in counts.iter().enumerate() {
+        for _ in 0..count {
+            i += 1;
+            index += BINOMIAL[rank + i - 1][i];
+        }
+    }
+    index as usize
+}
+
+// ランクの集合（13ビット）に含まれる最も高いストレートのトップ
+fn straight_high(mask: u16) -> Option<usize> {
+    (4..13)
+        .rev()
+        .find(|&high| mask >> (high - 4) & 0x1F == 0x1F)
+        .or(
+            // A-2-3-4-5 はエースを1と見なす
+            (mask & 0x100F == 0x100F).then_some(Rank::Five as usize),
+        )
+}
+
+// フラッシュがないときの、ランクの枚数から決まる最強の役
+fn strength_without_flush(counts: &[u8; 13]) -> HandStrength {
+    // 指定した枚数以上あるランクを強い順に（除くランク以外）
+    let ranks_with = |min: u8, except: &[usize]| -> Vec<usize> {
+        (0..13)
+            .rev()
+            .filter(|&r| counts[r] >= min && !except.contains(&r))
+            .collect()
     };
-    Some(Card { rank, suit })
+    let mask = (0..13)
+        .filter(|&r| counts[r] > 0)
+        .fold(0u16, |mask, r| mask | 1 << r);
+
+    if let Some(&quads) = ranks_with(4, &[]).first() {
+        return HandStrength::new(FOUR_OF_A_KIND, &[quads, ranks_with(1, &[quads])[0]]);
+    }
+    let trips = ranks_with(3, &[]);
+    if let Some(&trips) = trips.first()
+        && let Some(&pair) = ranks_with(2, &[trips]).first()
+    {
+        return HandStrength::new(FULL_HOUSE, &[trips, pair]);
+    }
+    if let Some(high) = straight_high(mask) {
+        return HandStrength::new(STRAIGHT, &[high]);
+    }
+    if let Some(&trips) = trips.first() {
+        let kickers = ranks_with(1, &[trips]);
+        return HandStrength::new(THREE_OF_A_KIND, &[trips, kickers[0], kickers[1]]);
+    }
+    let pairs = ranks_with(2, &[]);
+    if let [high, low, ..] = pairs[..] {
+        return HandStrength::new(TWO_PAIR, &[high, low, ranks_with(1, &[high, low])[0]]);
+    }
+    if let Some(&pair) = pairs.first() {
+        let kickers = ranks_with(1, &[pair]);
+        return HandStrength::new(ONE_PAIR, &[pair, kickers[0], kickers[1], kickers[2]]);
+    }
+    HandStrength::new(HIGH_CARD, &ranks_with(1, &[]))
+}
+
+// 5〜7枚のカードから作れる最強の役の強さを表引きで求める。割り当てやソートはしない
+pub fn evaluate_strength(cards: &[Card]) -> Option<HandStrength> {
+    if !(5..=7).contains(&cards.len()) {
+        return None;
+    }
+    let mut counts = [0u8; 13];
+    let mut suit_masks = [0u16; 4];
+    for card in cards {
+        counts[card.rank as usize] += 1;
+        suit_masks[card.suit as usize] |= 1 << card.rank as u16;
+    }
+    // 7枚以下では、同じスートが5枚以上あればフラッシュより強い役はストレートフラッシュしかない
+    if let Some(&mask) = suit_masks.iter().find(|mask| mask.count_ones() >= 5) {
+        return Some(TABLES.flush[mask as usize]);
+    }
+    Some(TABLES.ranks[cards.len() - 5][rank_index(&counts)])
 }
 
 // 7枚のカードから最強の5枚の役を見つける
@@ -117,97 +384,201 @@ pub fn evaluate_hand(seven_cards: &[Card]) -> Option<HandRank> {
     if seven_cards.len() != 7 {
         return None;
     }
-    let mut best_rank: Option<HandRank> = None;
+    evaluate_strength(seven_cards).map(HandStrength::hand_rank)
+}
 
-    // 7枚から5枚を選ぶ全ての組み合わせ (21通り) を試す
-    for i in 0..seven_cards.len() {
-        for j in (i + 1)..seven_cards.len() {
-            let mut hand: Vec<Card> = seven_cards.to_vec();
-            // 2枚を除外して5枚にする
-            hand.remove(j);
-            hand.remove(i);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::seq::SliceRandom;
+    use rand_chacha::ChaCha20Rng;
+    use std::collections::HashMap;
 
+    // 表引きにする前の判定。5枚を選ぶ全ての組み合わせを試す（比較用）
+    fn evaluate_by_brute_force(cards: &[Card]) -> HandRank {
+        let mut best_rank: Option<HandRank> = None;
+        for excluded in 0..1u32 << cards.len() {
+            if cards.len() - excluded.count_ones() as usize != 5 {
+                continue;
+            }
+            let mut hand: Vec<Card> = (0..cards.len())
+                .filter(|i| excluded & 1 << i == 0)
+                .map(|i| cards[i])
+                .collect();
             let current_rank = find_best_rank_for_5_cards(&mut hand);
-
             if best_rank.is_none() || current_rank > *best_rank.as_ref().unwrap() {
                 best_rank = Some(current_rank);
             }
         }
+        best_rank.unwrap()
     }
-    best_rank
-}
 
-// 5枚のカードの役を判定する
-fn find_best_rank_for_5_cards(hand: &mut [Card]) -> HandRank {
-    hand.sort_by(|a, b| b.rank.cmp(&a.rank)); // 降順ソート
-    let ranks: Vec<Rank> = hand.iter().map(|c| c.rank).collect();
-    let suits: Vec<Suit> = hand.iter().map(|c| c.suit).collect();
+    // 5枚のカードの役を判定する
+    fn find_best_rank_for_5_cards(hand: &mut [Card]) -> HandRank {
+        hand.sort_by_key(|c| std::cmp::Reverse(c.rank)); // 降順ソート
+        let ranks: Vec<Rank> = hand.iter().map(|c| c.rank).collect();
+        let suits: Vec<Suit> = hand.iter().map(|c| c.suit).collect();
 
-    let is_flush = suits.windows(2).all(|w| w[0] == w[1]);
-    let is_straight = ranks.windows(2).all(|w| w[0] as i8 == w[1] as i8 + 1);
+        let is_flush = suits.windows(2).all(|w| w[0] == w[1]);
+        let is_straight = ranks.windows(2).all(|w| w[0] as i8 == w[1] as i8 + 1);
 
-    // エースを1と見なすストレート (A-2-3-4-5) の特殊ケース
-    let is_ace_low_straight = ranks[0] == Rank::Ace
-        && ranks[1] == Rank::Five
-        && ranks[2] == Rank::Four
-        && ranks[3] == Rank::Three
-        && ranks[4] == Rank::Two;
+        // エースを1と見なすストレート (A-2-3-4-5) の特殊ケース
+        let is_ace_low_straight = ranks[0] == Rank::Ace
+            && ranks[1] == Rank::Five
+            && ranks[2] == Rank::Four
+            && ranks[3] == Rank::Three
+            && ranks[4] == Rank::Two;
 
-    if is_straight && is_flush {
-        if ranks[0] == Rank::Ace {
-            return HandRank::RoyalFlush;
+        if is_straight && is_flush {
+            if ranks[0] == Rank::Ace {
+                return HandRank::RoyalFlush;
+            }
+            return HandRank::StraightFlush(ranks[0]);
+        }
+        if is_ace_low_straight && is_flush {
+            return HandRank::StraightFlush(Rank::Five);
         }
-        return HandRank::StraightFlush(ranks[0]);
-    }
-    if is_ace_low_straight && is_flush {
-        return HandRank::StraightFlush(Rank::Five);
-    }
 
-    let mut counts: HashMap<Rank, u8> = HashMap::new();
-    for rank in &ranks {
-        *counts.entry(*rank).or_insert(0) += 1;
-    }
+        let mut counts: HashMap<Rank, u8> = HashMap::new();
+        for rank in &ranks {
+            *counts.entry(*rank).or_insert(0) += 1;
+        }
 
-    let mut pairs = Vec::new();
-    let mut threes = Vec::new();
-    let mut fours = Vec::new();
-    let mut kickers = Vec::new();
+        let mut pairs = Vec::new();
+        let mut threes = Vec::new();
+        let mut fours = Vec::new();
+        let mut kickers = Vec::new();
 
-    for (rank, count) in counts.iter() {
-        match count {
-            4 => fours.push(*rank),
-            3 => threes.push(*rank),
-            2 => pairs.push(*rank),
-            _ => kickers.push(*rank),
+        for (rank, count) in counts.iter() {
+            match count {
+                4 => fours.push(*rank),
+                3 => threes.push(*rank),
+                2 => pairs.push(*rank),
+                _ => kickers.push(*rank),
+            }
         }
-    }
 
-    pairs.sort_by(|a, b| b.cmp(a));
-    kickers.sort_by(|a, b| b.cmp(a));
+        pairs.sort_by(|a, b| b.cmp(a));
+        kickers.sort_by(|a, b| b.cmp(a));
 
-    if !fours.is_empty() {
-        return HandRank::FourOfAKind(fours[0], kickers[0]);
-    }
-    if !threes.is_empty() && !pairs.is_empty() {
-        return HandRank::FullHouse(threes[0], pairs[0]);
-    }
-    if is_flush {
-        return HandRank::Flush(ranks[0], ranks[1], ranks[2], ranks[3], ranks[4]);
+        if !fours.is_empty() {
+            return HandRank::FourOfAKind(fours[0], kickers[0]);
+        }
+        if !threes.is_empty() && !pairs.is_empty() {
+            return HandRank::FullHouse(threes[0], pairs[0]);
+        }
+        if is_flush {
+            return HandRank::Flush(ranks[0], ranks[1], ranks[2], ranks[3], ranks[4]);
+        }
+        if is_straight {
+            return HandRank::Straight(ranks[0]);
+        }
+        if is_ace_low_straight {
+            return HandRank::Straight(Rank::Five);
+        }
+        if !threes.is_empty() {
+            return HandRank::ThreeOfAKind(threes[0], kickers[0], kickers[1]);
+        }
+        if pairs.len() >= 2 {
+            return HandRank::TwoPair(pairs[0], pairs[1], kickers[0]);
+        }
+        if !pairs.is_empty() {
+            return HandRank::OnePair(pairs[0], kickers[0], kickers[1], kickers[2]);
+        }
+        HandRank::HighCard(ranks[0], ranks[1], ranks[2], ranks[3], ranks[4])
     }
-    if is_straight {
-        return HandRank::Straight(ranks[0]);
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_whitespace().map(|c| c.parse().unwrap()).collect()
     }
-    if is_ace_low_straight {
-        return HandRank::Straight(Rank::Five);
+
+    // 表引きの判定が、総当たりの判定と役・強さの順序の両方で一致することを確かめる
+    fn assert_agrees(deck: &[Card], hands: usize, seed: u64) {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let mut previous: Option<(HandStrength, HandRank)> = None;
+        for i in 0..hands {
+            let hand: Vec<Card> = deck.choose_multiple(&mut rng, 5 + i % 3).copied().collect();
+            let strength = evaluate_strength(&hand).unwrap();
+            let rank = evaluate_by_brute_force(&hand);
+            assert_eq!(strength.hand_rank(), rank, "{:?}", hand);
+            if let Some((previous_strength, previous_rank)) = previous {
+                assert_eq!(
+                    strength.cmp(&previous_strength),
+                    rank.cmp(&previous_rank),
+                    "{:?}",
+                    hand
+                );
+            }
+            previous = Some((strength, rank));
+        }
     }
-    if !threes.is_empty() {
-        return HandRank::ThreeOfAKind(threes[0], kickers[0], kickers[1]);
+
+    #[test]
+    fn lookup_agrees_with_brute_force_on_sampled_hands() {
+        let deck: Vec<Card> = Suit::ALL
+            .into_iter()
+            .flat_map(|suit| Rank::ALL.into_iter().map(move |rank| Card { rank, suit }))
+            .collect();
+        assert_agrees(&deck, 50_000, 1);
+        // 2スートだけのデッキからも引いて、フラッシュやストレートフラッシュを多く試す
+        assert_agrees(&deck[..26], 20_000, 2);
     }
-    if pairs.len() >= 2 {
-        return HandRank::TwoPair(pairs[0], pairs[1], kickers[0]);
+
+    #[test]
+    fn lookup_agrees_with_brute_force_on_edge_cases() {
+        for hand in [
+            "AH 2H 3H 4H 5H KD KC", // A-5のストレートフラッシュ
+            "AS KS QS JS TS 9S 8S", // ロイヤルフラッシュ（7枚とも同じスート）
+            "9C 8C 7C 6C 5C 4C AD", // 6枚のストレートフラッシュ
+            "AD KD 9D 5D 3D 2D 4C", // フラッシュとストレートの両方
+            "AC 2D 3H 4S 5C 5D 5H", // スリーカードとA-5のストレート
+            "KC KD KH QS QC QD 2H", // スリーカード2組
+            "KC KD QH QS JC JD 9H", // ツーペア3組（キッカーは3組目のペア）
+            "7C 7D 7H 7S KC KD KH", // フォーカードとスリーカード
+            "2C 3D 4H 6S 7C 9D JH", // ハイカード
+        ] {
+            let hand = cards(hand);
+            assert_eq!(
+                evaluate_hand(&hand),
+                Some(evaluate_by_brute_force(&hand)),
+                "{:?}",
+                hand
+            );
+        }
+        assert_eq!(evaluate_strength(&cards("AH KH QH JH")), None);
+        assert_eq!(evaluate_hand(&cards("AH KH QH JH TH")), None);
     }
-    if !pairs.is_empty() {
-        return HandRank::OnePair(pairs[0], kickers[0], kickers[1], kickers[2]);
+
+    #[test]
+    fn cards_are_sent_as_two_letter_strings() {
+        let hand = vec![
+            Card {
+                rank: Rank::Ace,
+                suit: Suit::Spade,
+            },
+            Card {
+                rank: Rank::Ten,
+                suit: Suit::Diamond,
+            },
+            Card {
+                rank: Rank::Two,
+                suit: Suit::Club,
+            },
+        ];
+        let json = serde_json::to_string(&hand).unwrap();
+        assert_eq!(json, r#"["AS","TD","2C"]"#);
+        assert_eq!(serde_json::from_str::<Vec<Card>>(&json).unwrap(), hand);
+
+        // 文字数・ランク・スート（小文字を含む）が不正な表記や、文字列でない値は受け付けない
+        for malformed in [
+            r#""""#, r#""A""#, r#""10S""#, r#""1S""#, r#""AX""#, r#""as""#, "14",
+        ] {
+            assert!(
+                serde_json::from_str::<Card>(malformed).is_err(),
+                "{}",
+                malformed
+            );
+        }
     }
-    HandRank::HighCard(ranks[0], ranks[1], ranks[2], ranks[3], ranks[4])
 }
@@ -0,0 +1,221 @@
+use crate::fairness::ShuffleProof;
+use crate::game::{GamePhase, PlayerAction};
+use crate::hand_evaluator::Card;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ハンド開始時の着席情報
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Seat {
+    pub seat: usize, // テーブル上の席順（0始まり）
+    pub username: String,
+    pub stack: u32, // ハンド開始時のスタック
+}
+
+// ハンド中に起きた出来事。リプレイ時はこの順に再生する
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum HandEvent {
+    PostAnte {
+        username: String,
+        amount: u32,
+    },
+    PostSmallBlind {
+        username: String,
+        amount: u32,
+    },
+    PostBigBlind {
+        username: String,
+        amount: u32,
+    },
+    // 休憩から戻ったプレイヤーが払う、払わなかったブラインド
+    PostMissedBlinds {
+        username: String,
+        live: u32, // ベット額に数える（BB）
+        dead: u32, // ポットに入るだけ（SB）
+    },
+    DealHoleCards {
+        username: String,
+        cards: Vec<Card>,
+    },
+    PlayerAction {
+        street: GamePhase,
+        username: String,
+        #[serde(flatten)]
+        action: PlayerAction,
+        chips: u32, // このアクションで出したチップ
+        is_all_in: bool,
+    },
+    DealBoard {
+        street: GamePhase,
+        cards: Vec<Card>,
+    },
+    Showdown {
+        username: String,
+        cards: Vec<Card>,
+        hand_rank: String,
+    },
+    // ショーダウンで手札を見せずに捨てた
+    MuckCards {
+        username: String,
+    },
+    // 誰にもコールされなかったベットを返した（ポットには含めない）
+    ReturnUncalledBet {
+        username: String,
+        amount: u32,
+    },
+    CollectPot {
+        username: String,
+        amount: u32,
+        pot_index: usize, // 0がメインポット
+    },
+}
+
+// 1ハンド分の記録
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct HandHistory {
+    pub started_at: DateTime<Utc>,
+    pub dealer: String, // ボタンのプレイヤー（デッドボタンなら空）
+    #[serde(default)] // 席番号を記録する前のハンドにはない
+    pub button_seat: Option<usize>,
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub ante: u32,
+    #[serde(default)] // デッキのシャッフルに使ったシード（16進数）。デッキを指定したハンドにはない
+    pub seed: Option<String>,
+    #[serde(default)]
+    // シャッフルの証明（ハンド後に公開したサーバーシードと、混ぜたプレイヤーのシード）
+    pub shuffle: Option<ShuffleProof>,
+    pub seats: Vec<Seat>,
+    pub board: Vec<Card>,
+    pub events: Vec<HandEvent>,
+}
+
+// アクションの種類。オールインは出した額に応じてコール・ベット・レイズのどれかに分類する
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    Fold,
+    Check,
+    Call,
+    Bet,
+    Raise,
+}
+
+// 種類を判定したアクション（統計やエクスポートで使う）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassifiedAction<'a> {
+    pub street: GamePhase,
+    pub username: &'a str,
+    pub kind: ActionKind,
+    pub chips: u32,  // このアクションで出したチップ
+    pub facing: u32, // アクション前のこのストリートの最高ベット額
+    pub to: u32,     // アクション後のこのストリートでの合計ベット額
+    pub is_all_in: bool,
+}
+
+impl HandHistory {
+    // 指定したプレイヤーから見える記録。他のプレイヤーの手札は、ショーダウンで見せたもの以外をリプレイに出さない。
+    // シードとシャッフルの証明は誰でも検証できるよう残す（ハンド後はそこから見せなかった手札も分かる）
+    pub fn visible_to(mut self, username: &str) -> Self {
+        self.events.retain(
+            |e| !matches!(e, HandEvent::DealHoleCards { username: u, .. } if u != username),
+        );
+        self
+    }
+
+    // 記録されたアクションを順に種類分けする
+    pub fn classified_actions(&self) -> Vec<ClassifiedAction<'_>> {
+        let mut actions = Vec::new();
+        let mut street_bets: HashMap<&str, u32> = HashMap::new();
+        for event in &self.events {
+            match event {
+                HandEvent::PostSmallBlind { username, amount }
+                | HandEvent::PostBigBlind { username, amount } => {
+                    *street_bets.entry(username).or_default() += amount;
+                }
+                HandEvent::PostMissedBlinds { username, live, .. } => {
+                    *street_bets.entry(username).or_default() += live;
+                }
+                HandEvent::DealBoard { .. } => street_bets.clear(),
+                HandEvent::PlayerAction {
+                    street,
+                    username,
+                    action,
+                    chips,
+                    is_all_in,
+                } => {
+                    let facing = street_bets.values().copied().max().unwrap_or(0);
+                    let bet = street_bets.entry(username).or_default();
+                    *bet += chips;
+                    let to = *bet;
+                    let kind = match action {
+                        PlayerAction::Fold => ActionKind::Fold,
+                        _ if *chips == 0 => ActionKind::Check,
+                        _ if to <= facing => ActionKind::Call,
+                        _ if facing == 0 => ActionKind::Bet,
+                        _ => ActionKind::Raise,
+                    };
+                    actions.push(ClassifiedAction {
+                        street: *street,
+                        username,
+                        kind,
+                        chips: *chips,
+                        facing,
+                        to,
+                        is_all_in: *is_all_in,
+                    });
+                }
+                _ => {}
+            }
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand() -> HandHistory {
+        HandHistory {
+            seed: Some("00".repeat(32)),
+            shuffle: Some(ShuffleProof {
+                server_seed_hash: "11".repeat(32),
+                server_seed: Some("22".repeat(32)),
+                client_seeds: Vec::new(),
+            }),
+            seats: ["alice", "bob"]
+                .into_iter()
+                .enumerate()
+                .map(|(seat, username)| Seat {
+                    seat,
+                    username: username.to_string(),
+                    stack: 1000,
+                })
+                .collect(),
+            events: ["alice", "bob"]
+                .into_iter()
+                .map(|username| HandEvent::DealHoleCards {
+                    username: username.to_string(),
+                    cards: Vec::new(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn players_see_only_their_own_hole_cards() {
+        let visible = hand().visible_to("alice");
+        assert_eq!(visible.events, hand().events[..1]);
+    }
+
+    #[test]
+    fn other_users_see_no_hole_cards_but_keep_the_seed_for_verification() {
+        let visible = hand().visible_to("zzz");
+        assert_eq!(visible.seed, hand().seed);
+        assert_eq!(visible.shuffle, hand().shuffle);
+        assert!(visible.events.is_empty());
+    }
+}
@@ -1,4 +1,12 @@
-use crate::game::{GameMessage, GameState, PlayerAction};
+use crate::fairness::ShuffleProof;
+use crate::game::{
+    GameError, GameMessage, GamePhase, GameState, HandResult, PlayerAction, TableConfig,
+};
+use crate::hand_evaluator::Card;
+use crate::hand_history::HandHistory;
+use crate::session::SessionRegistry;
+use crate::stats::{PlayerStats, StatsCounter};
+use crate::tournament::{FinishingPlace, TournamentConfig};
 use axum::http::{Method, header};
 use axum::{
     Json, Router,
@@ -12,22 +20,29 @@ use axum::{
 };
 use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
 use chrono::{Duration, Utc};
-use dashmap::DashMap;
+use dashmap::{DashMap, mapref::entry::Entry};
 use dotenvy::dotenv;
 use futures_util::{SinkExt, stream::StreamExt};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use sqlx::postgres::PgPoolOptions;
+use std::collections::VecDeque;
 use std::env;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use time;
 use tokio::sync::{Mutex, broadcast, mpsc};
+use tokio::time::Instant;
 use tower_http::cors::CorsLayer;
 
+mod fairness;
 mod game;
 mod hand_evaluator;
+mod hand_history;
+mod pokerstars;
+mod session;
+mod stats;
+mod tournament;
 
 // --- 構造体の定義 ---
 
@@ -53,6 +68,8 @@ struct UserAuth {
 #[derive(Deserialize)]
 struct CreateRoomPayload {
     name: String,
+    #[serde(flatten)]
+    config: TableConfig, // 省略した項目はデフォルト値
 }
 
 #[derive(Serialize, sqlx::FromRow)]
@@ -62,6 +79,87 @@ struct Room {
     status: String,
     created_by: uuid::Uuid,
     created_at: time::OffsetDateTime,
+    small_blind: i32,
+    big_blind: i32,
+    ante: i32,
+    starting_stack: i32,
+    max_seats: i32,
+    min_players: i32,
+    tournament: Option<sqlx::types::Json<TournamentConfig>>,
+    action_timeout_secs: i32,
+    time_bank_secs: i32,
+    spectator_delay_secs: i32,
+}
+
+impl Room {
+    // DBに保存されたテーブル設定をゲーム用の設定に変換
+    fn table_config(&self) -> TableConfig {
+        TableConfig {
+            small_blind: self.small_blind as u32,
+            big_blind: self.big_blind as u32,
+            ante: self.ante as u32,
+            starting_stack: self.starting_stack as u32,
+            max_seats: self.max_seats as usize,
+            min_players: self.min_players as usize,
+            action_timeout_secs: self.action_timeout_secs as u32,
+            time_bank_secs: self.time_bank_secs as u32,
+            spectator_delay_secs: self.spectator_delay_secs as u32,
+            tournament: self.tournament.as_ref().map(|t| t.0.clone()),
+        }
+    }
+}
+
+// ルーム情報に、現在の観戦者数を加えたもの
+#[derive(Serialize)]
+struct RoomInfo {
+    #[serde(flatten)]
+    room: Room,
+    spectator_count: usize,
+}
+
+// 戦績一覧のページ指定
+#[derive(Deserialize)]
+struct HistoryQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+// 戦績の1件（1ハンド分）
+#[derive(Serialize, sqlx::FromRow)]
+struct HistoryEntry {
+    game_result_id: uuid::Uuid,
+    room_id: uuid::Uuid,
+    room_name: String,
+    pot: i32,
+    winning_hand_rank: Option<String>,
+    finished_at: time::OffsetDateTime,
+    net_chips: i32,
+    hand_rank: Option<String>,
+    is_winner: bool,
+    participants: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct HistoryPage {
+    results: Vec<HistoryEntry>,
+    total: i64,
+    limit: i64,
+    offset: i64,
+}
+
+// リプレイ用のハンド記録
+#[derive(Serialize, sqlx::FromRow)]
+struct HandReplay {
+    id: uuid::Uuid,
+    room_id: uuid::Uuid,
+    finished_at: time::OffsetDateTime,
+    history: sqlx::types::Json<HandHistory>,
+}
+
+// シャッフルの検証結果
+#[derive(Serialize)]
+struct VerifiedShuffle {
+    cards: Vec<Card>, // 配られる順
 }
 
 // WebSocket接続を管理するための状態
@@ -72,13 +170,16 @@ struct AppState {
     chat_rooms: Arc<DashMap<uuid::Uuid, broadcast::Sender<String>>>,
     // ゲーム状態管理用 (Mutexで保護)
     game_states: Arc<DashMap<uuid::Uuid, Arc<Mutex<GameState>>>>,
-    player_senders: Arc<DashMap<String, mpsc::Sender<String>>>,
+    // 個別メッセージ（手札など）の送信先。ルームごと・接続ごとに管理
+    sessions: Arc<SessionRegistry>,
 }
 
 // WebSocket認証用のクエリパラメータ
 #[derive(Deserialize)]
 struct WebSocketAuth {
     token: Option<String>,
+    #[serde(default)]
+    spectate: bool, // trueなら着席せずに観戦する
 }
 
 // --- JWT Claims Extractor ---
@@ -143,7 +244,7 @@ async fn main() {
         db_pool: pool.clone(),
         chat_rooms: Arc::new(DashMap::new()),
         game_states: Arc::new(DashMap::new()),
-        player_senders: Arc::new(DashMap::new()),
+        sessions: Arc::new(SessionRegistry::default()),
     });
 
     // CORSの設定
@@ -174,8 +275,14 @@ async fn main() {
         .route("/api/login", post(login))
         .route("/api/logout", post(logout))
         .route("/api/me", get(get_me))
+        .route("/api/me/history", get(get_my_history))
+        .route("/api/users/{username}/history", get(get_user_history))
+        .route("/api/users/{username}/stats", get(get_user_stats))
         .route("/api/rooms", post(create_room).get(get_rooms))
         .route("/api/rooms/{id}", get(get_room_by_id))
+        .route("/api/rooms/{id}/hand_histories", get(export_hand_histories))
+        .route("/api/hands/{id}", get(get_hand_by_id))
+        .route("/api/shuffle/verify", post(verify_shuffle))
         .route("/api/ws/rooms/{room_id}", get(ws_handler))
         .layer(cors)
         .with_state(app_state);
@@ -216,8 +323,27 @@ async fn ws_handler(
         }
     };
 
+    // ルームのテーブル設定を取得
+    let room = match sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1")
+        .bind(room_id)
+        .fetch_optional(&state.db_pool)
+        .await
+    {
+        Ok(Some(room)) => room,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Room not found").into_response(),
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to fetch room: {}", e),
+            )
+                .into_response();
+        }
+    };
+    let config = room.table_config();
+
     println!("WebSocket connection established for user: {}", claims.sub);
-    ws.on_upgrade(move |socket| handle_socket(socket, state, claims, room_id))
+    let is_spectator = auth.spectate;
+    ws.on_upgrade(move |socket| handle_socket(socket, state, claims, room_id, config, is_spectator))
 }
 
 // 実際のWebSocket通信を処理する関数
@@ -226,15 +352,22 @@ async fn handle_socket(
     state: Arc<AppState>,
     claims: Claims,
     room_id: uuid::Uuid,
+    config: TableConfig,
+    is_spectator: bool,
 ) {
     let username = claims.sub;
+    let spectator_delay = std::time::Duration::from_secs(config.spectator_delay_secs as u64);
+    // 観戦者に遅れて送るメッセージ（送信予定時刻順）
+    let mut delayed: VecDeque<(Instant, String)> = VecDeque::new();
 
     // --- 接続セットアップ ---
     let (mut ws_sender, mut ws_receiver) = socket.split();
 
     // 1. このクライアント専用のメッセージチャネルを作成
     let (private_tx, mut private_rx) = mpsc::channel::<String>(10);
-    state.player_senders.insert(username.clone(), private_tx);
+    let connection_id = state
+        .sessions
+        .register(room_id, &username, private_tx, is_spectator);
 
     // 2. 公開メッセージ用のブロードキャストチャネルを取得
     let broadcast_tx = state
@@ -245,72 +378,151 @@ async fn handle_socket(
     let mut broadcast_rx = broadcast_tx.subscribe();
 
     // 3. ゲーム状態のロックを取得
-    let game_state_lock = state
-        .game_states
-        .entry(room_id)
-        .or_insert_with(|| Arc::new(Mutex::new(GameState::new())))
-        .value()
-        .clone();
+    //    ルームが新しく作られた場合は持ち時間タイマーも起動する
+    let game_state_lock = match state.game_states.entry(room_id) {
+        Entry::Occupied(entry) => entry.get().clone(),
+        Entry::Vacant(entry) => {
+            let game_state_lock = Arc::new(Mutex::new(GameState::new(config)));
+            entry.insert(game_state_lock.clone());
+            tokio::spawn(run_action_timer(
+                state.clone(),
+                room_id,
+                game_state_lock.clone(),
+            ));
+            game_state_lock
+        }
+    };
 
     // --- 接続時の初期処理 ---
-    {
+    if is_spectator {
+        // 観戦者は着席せず、現在の状態だけを受け取る
+        let game = game_state_lock.lock().await;
+        let update_msg = GameMessage::GameStateUpdate(Box::new(game.sanitized()));
+        let json = serde_json::to_string(&update_msg).unwrap();
+        delayed.push_back((Instant::now() + spectator_delay, json));
+        let _ = broadcast_tx.send(format!("{}さんが観戦を始めました。", username));
+    } else {
+        // 着席はTakeSeatで行う。すでに席がある（再接続した）場合は席に戻る
         let mut game = game_state_lock.lock().await;
-        game.add_player(username.clone());
+        if game.reconnect(&username) {
+            // ハンドの途中で再接続した場合は手札を送り直す
+            let hand = game
+                .players
+                .iter()
+                .find(|p| p.username == username)
+                .map(|p| p.hand.clone())
+                .unwrap_or_default();
+            if !hand.is_empty() {
+                let hand_msg = GameMessage::DealHand(game::DealHandPayload { cards: hand });
+                let json = serde_json::to_string(&hand_msg).unwrap();
+                let _ = ws_sender.send(Message::Text(json.into())).await;
+            }
+        }
 
         // 全員に更新されたゲーム状態をブロードキャスト
-        let update_msg = GameMessage::GameStateUpdate(game.sanitized());
-        let json = serde_json::to_string(&update_msg).unwrap();
-        let _ = broadcast_tx.send(json);
+        broadcast_game_state(&state, room_id, &broadcast_tx, &game).await;
+        let _ = broadcast_tx.send(format!("{}さんが入室しました。", username));
+    }
+
+    // 着席しているプレイヤーの統計を全員に送る
+    {
+        let game = game_state_lock.lock().await;
+        let usernames: Vec<String> = game.players.iter().map(|p| p.username.clone()).collect();
+        let db_pool = state.db_pool.clone();
+        let stats_tx = broadcast_tx.clone();
+        tokio::spawn(async move {
+            broadcast_player_stats(&db_pool, &stats_tx, &usernames).await;
+        });
     }
-    let _ = broadcast_tx.send(format!("{}さんが入室しました。", username));
 
     // --- メインループ ---
     loop {
         tokio::select! {
             // A. クライアントからメッセージを受信した場合
-            Some(Ok(msg)) = ws_receiver.next() => {
+            msg = ws_receiver.next() => {
+                let Some(Ok(msg)) = msg else {
+                    break; // 接続が切れた
+                };
                 if let Message::Text(text) = msg {
-                    match serde_json::from_str::<GameMessage>(&text) {
+                    let result = match serde_json::from_str::<GameMessage>(&text) {
+                        Ok(GameMessage::PlayerAction(_)) if is_spectator => Err(GameError::Spectating),
                         Ok(GameMessage::PlayerAction(action)) => {
                             let mut game = game_state_lock.lock().await;
-                            // StartGame以外のPlayerActionはhandle_actionで処理
-                            match action {
-                                PlayerAction::StartGame => {
-                                    game.start_game();
-
-                                    // 全プレイヤーに個別に手札を送信
-                                    for player in &game.players {
-                                        if let Some(sender) = state.player_senders.get(&player.username) {
-                                            let hand_msg = GameMessage::DealHand(game::DealHandPayload {
-                                                cards: player.hand.clone(),
-                                            });
-                                            let json = serde_json::to_string(&hand_msg).unwrap();
-                                            let _ = sender.send(json).await;
-                                        }
+                            let is_start_game = matches!(action, PlayerAction::StartGame);
+                            let was_finished = game.status() == GamePhase::Finished;
+                            let result = game.handle_action(&username, action);
+                            if result.is_ok() {
+                                // 手札を配られたプレイヤーに個別に送信（休憩中・チップのないプレイヤーには送らない）
+                                if is_start_game {
+                                    for player in game.players.iter().filter(|p| !p.hand.is_empty()) {
+                                        let hand_msg = GameMessage::DealHand(game::DealHandPayload {
+                                            cards: player.hand.clone(),
+                                        });
+                                        let json = serde_json::to_string(&hand_msg).unwrap();
+                                        state.sessions.send_to_user(room_id, &player.username, json).await;
                                     }
-                                },
-                                // ★ Foldなどのアクションを処理
-                                _ => {
-                                    game.handle_action(&username, action);
                                 }
+                                // ★ 更新されたゲーム状態をブロードキャスト
+                                broadcast_game_state(&state, room_id, &broadcast_tx, &game).await;
+                                save_hand_results(&state, room_id, game.take_completed_hands());
+                                announce_cash_outs(&broadcast_tx, &mut game);
+
+                                // トーナメントが終了したら順位を保存し、ルームを終了状態にする
+                                if !was_finished
+                                    && game.status() == GamePhase::Finished
+                                    && let Some(tournament) = &game.tournament
+                                {
+                                    save_tournament_results(&state, room_id, tournament.finishing_places.clone());
+                                }
+                            }
+                            result
+                        }
+                        Ok(GameMessage::ClientSeed(_)) if is_spectator => Err(GameError::Spectating),
+                        Ok(GameMessage::ClientSeed(seed)) => {
+                            let mut game = game_state_lock.lock().await;
+                            let result = game.set_client_seed(&username, seed);
+                            if result.is_ok() {
+                                broadcast_game_state(&state, room_id, &broadcast_tx, &game).await;
                             }
-                            // ★ 更新されたゲーム状態をブロードキャスト
-                            let update_msg = GameMessage::GameStateUpdate(game.sanitized());
-                            let json = serde_json::to_string(&update_msg).unwrap();
-                            let _ = broadcast_tx.send(json);
+                            result
                         }
                         Ok(GameMessage::ChatMessage(chat_msg)) => {
                             let _ = broadcast_tx.send(format!("{}: {}", username, chat_msg));
+                            Ok(())
+                        }
+                        _ => Err(GameError::InvalidMessage),
+                    };
+
+                    // 無効な操作はそのクライアントにだけエラーを返す
+                    if let Err(error) = result {
+                        let error_msg = GameMessage::Error(error.into());
+                        let json = serde_json::to_string(&error_msg).unwrap();
+                        if ws_sender.send(Message::Text(json.into())).await.is_err() {
+                            break;
                         }
-                        _ => {}
                     }
                 } else if let Message::Close(_) = msg {
                     break;
                 }
             },
             // B. ブロードキャストメッセージを受信した場合
+            //    観戦フラグに関係なく、その時点で着席していない接続にはディレイをかける
             Ok(msg) = broadcast_rx.recv() => {
-                if ws_sender.send(Message::Text(msg.into())).await.is_err() {
+                let is_delayed = !spectator_delay.is_zero()
+                    && !state.sessions.is_seated(room_id, &username);
+                if is_delayed {
+                    delayed.push_back((Instant::now() + spectator_delay, msg));
+                    continue;
+                }
+                // 着席する前に遅らせていたメッセージを先に送り、順序を保つ
+                let mut is_closed = false;
+                for (_, delayed_msg) in delayed.drain(..).chain([(Instant::now(), msg)]) {
+                    if ws_sender.send(Message::Text(delayed_msg.into())).await.is_err() {
+                        is_closed = true;
+                        break;
+                    }
+                }
+                if is_closed {
                     break;
                 }
             },
@@ -319,20 +531,309 @@ async fn handle_socket(
                 if ws_sender.send(Message::Text(msg.into())).await.is_err() {
                     break;
                 }
+            },
+            // D. ディレイ時間が過ぎたメッセージを着席していない接続に送る
+            _ = tokio::time::sleep_until(delayed.front().map_or_else(Instant::now, |(due, _)| *due)),
+                if !delayed.is_empty() =>
+            {
+                let mut is_closed = false;
+                while let Some((due, _)) = delayed.front()
+                    && *due <= Instant::now()
+                {
+                    let (_, msg) = delayed.pop_front().unwrap();
+                    if ws_sender.send(Message::Text(msg.into())).await.is_err() {
+                        is_closed = true;
+                        break;
+                    }
+                }
+                if is_closed {
+                    break;
+                }
             }
         }
     }
 
     // --- 切断時の処理 ---
-    state.player_senders.remove(&username);
+    // 別のタブなどでこのルームに接続が残っている場合は離席扱いにしない
+    let is_last_connection = state.sessions.unregister(room_id, connection_id);
+    if is_spectator {
+        let _ = broadcast_tx.send(format!("{}さんが観戦をやめました。", username));
+        return;
+    }
+    if is_last_connection {
+        // 席は猶予時間の間残し、ルームの片付けは持ち時間タイマーが行う
+        let mut game = game_state_lock.lock().await;
+        game.mark_disconnected(&username, Utc::now());
+        broadcast_game_state(&state, room_id, &broadcast_tx, &game).await;
+    }
     let _ = broadcast_tx.send(format!("{}さんが退出しました。", username));
-    if broadcast_tx.receiver_count() == 1 {
-        state.chat_rooms.remove(&room_id);
-        state.game_states.remove(&room_id);
-        println!("Room {} is now empty and removed.", room_id);
+}
+
+// ルームごとの持ち時間タイマー。手番のプレイヤーが時間切れ・離席中なら自動でアクションし、
+// 戻らないプレイヤーの席を外す。全員がいなくなったらルームを片付けて終了する
+async fn run_action_timer(
+    state: Arc<AppState>,
+    room_id: uuid::Uuid,
+    game_state_lock: Arc<Mutex<GameState>>,
+) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+
+        // ルームが削除（または作り直し）されたらタイマーも終了する
+        let is_current_room = state
+            .game_states
+            .get(&room_id)
+            .is_some_and(|entry| Arc::ptr_eq(entry.value(), &game_state_lock));
+        if !is_current_room {
+            break;
+        }
+
+        let now = Utc::now();
+        let mut game = game_state_lock.lock().await;
+        let timed_out = game.handle_timeout(now);
+        let ran_out = game.advance_runout(now);
+        let removed = game.remove_expired_players(now);
+        let broadcast_tx = state.chat_rooms.get(&room_id).map(|tx| tx.clone());
+        if let Some(broadcast_tx) = &broadcast_tx
+            && (timed_out || ran_out || removed)
+        {
+            broadcast_game_state(&state, room_id, broadcast_tx, &game).await;
+        }
+        save_hand_results(&state, room_id, game.take_completed_hands());
+        if let Some(broadcast_tx) = &broadcast_tx {
+            announce_cash_outs(broadcast_tx, &mut game);
+        }
+
+        // 接続中のクライアントがおらず、全員の猶予時間が過ぎたらルームを削除
+        let has_connections = broadcast_tx.is_some_and(|tx| tx.receiver_count() > 0);
+        if !has_connections && game.is_abandoned(now) {
+            state.chat_rooms.remove(&room_id);
+            state.game_states.remove(&room_id);
+            state.sessions.remove_room(room_id);
+            println!("Room {} is now empty and removed.", room_id);
+            break;
+        }
+    }
+}
+
+// 更新されたゲーム状態を全員に送り、手番のプレイヤーには取れるアクションも送る
+async fn broadcast_game_state(
+    state: &AppState,
+    room_id: uuid::Uuid,
+    broadcast_tx: &broadcast::Sender<String>,
+    game: &GameState,
+) {
+    // ディレイをかけるかどうかは受信側がこの着席情報で判定する
+    let seated = game.players.iter().map(|p| p.username.clone()).collect();
+    state.sessions.set_seated(room_id, seated);
+    let update_msg = GameMessage::GameStateUpdate(Box::new(game.sanitized()));
+    let json = serde_json::to_string(&update_msg).unwrap();
+    let _ = broadcast_tx.send(json);
+    send_legal_actions(state, room_id, game).await;
+}
+
+// 手番のプレイヤーにだけ、取れるアクションの一覧を送信する
+async fn send_legal_actions(state: &AppState, room_id: uuid::Uuid, game: &GameState) {
+    let Some(legal_actions) = game.legal_actions() else {
+        return;
+    };
+    let username = legal_actions.username.clone();
+    let msg = GameMessage::LegalActions(legal_actions);
+    let json = serde_json::to_string(&msg).unwrap();
+    state.sessions.send_to_user(room_id, &username, json).await;
+}
+
+// 席を立ったプレイヤーと持ち帰ったチップをルームに知らせる
+fn announce_cash_outs(broadcast_tx: &broadcast::Sender<String>, game: &mut GameState) {
+    for cash_out in game.take_cash_outs() {
+        let _ = broadcast_tx.send(format!(
+            "{}さんが{}チップを持って席を立ちました。",
+            cash_out.username, cash_out.stack
+        ));
     }
 }
 
+// 終了したハンドの結果を戦績としてDBに保存する。ゲームのロックを待たせないよう別タスクで行う
+fn save_hand_results(state: &AppState, room_id: uuid::Uuid, results: Vec<HandResult>) {
+    if results.is_empty() {
+        return;
+    }
+    let db_pool = state.db_pool.clone();
+    let broadcast_tx = state.chat_rooms.get(&room_id).map(|tx| tx.clone());
+    tokio::spawn(async move {
+        let mut usernames: Vec<String> = Vec::new();
+        for result in results {
+            if let Err(e) = insert_hand_result(&db_pool, room_id, &result).await {
+                eprintln!("Failed to save hand result: {}", e);
+            }
+            for player in result.players {
+                if !usernames.contains(&player.username) {
+                    usernames.push(player.username);
+                }
+            }
+        }
+        // 保存したハンドを反映した統計をテーブルに送る
+        if let Some(broadcast_tx) = broadcast_tx {
+            broadcast_player_stats(&db_pool, &broadcast_tx, &usernames).await;
+        }
+    });
+}
+
+// 指定したプレイヤーの統計をルーム全員に送る
+async fn broadcast_player_stats(
+    db_pool: &PgPool,
+    broadcast_tx: &broadcast::Sender<String>,
+    usernames: &[String],
+) {
+    let mut all_stats = Vec::new();
+    for username in usernames {
+        match load_player_stats(db_pool, username).await {
+            Ok(stats) => all_stats.push(stats),
+            Err(e) => eprintln!("Failed to load stats for {}: {}", username, e),
+        }
+    }
+    if all_stats.is_empty() {
+        return;
+    }
+    let msg = GameMessage::PlayerStats(all_stats);
+    let json = serde_json::to_string(&msg).unwrap();
+    let _ = broadcast_tx.send(json);
+}
+
+// ハンドごとに保存した集計値を合計して統計を求める
+async fn load_player_stats(db_pool: &PgPool, username: &str) -> Result<PlayerStats, sqlx::Error> {
+    let totals: (i64, i64, i64, i64, i64, i64, i64, i64, i64, i64, f64) = sqlx::query_as(
+        "SELECT COALESCE(SUM(hands), 0), COALESCE(SUM(vpip_hands), 0), COALESCE(SUM(pfr_hands), 0), \
+         COALESCE(SUM(three_bet_opportunities), 0), COALESCE(SUM(three_bets), 0), \
+         COALESCE(SUM(postflop_aggressive), 0), COALESCE(SUM(postflop_calls), 0), \
+         COALESCE(SUM(saw_flop), 0), COALESCE(SUM(went_to_showdown), 0), \
+         COALESCE(SUM(won_at_showdown), 0), COALESCE(SUM(total_bb), 0) \
+         FROM game_result_players grp JOIN users u ON u.id = grp.user_id WHERE u.username = $1",
+    )
+    .bind(username)
+    .fetch_one(db_pool)
+    .await?;
+    let counter = StatsCounter {
+        hands: totals.0 as u32,
+        vpip_hands: totals.1 as u32,
+        pfr_hands: totals.2 as u32,
+        three_bet_opportunities: totals.3 as u32,
+        three_bets: totals.4 as u32,
+        postflop_aggressive: totals.5 as u32,
+        postflop_calls: totals.6 as u32,
+        saw_flop: totals.7 as u32,
+        went_to_showdown: totals.8 as u32,
+        won_at_showdown: totals.9 as u32,
+        total_bb: totals.10,
+    };
+    Ok(counter.finish(username))
+}
+
+// 1ハンド分の集計値を、そのハンドの参加者の結果に保存する
+async fn save_hand_stats(
+    executor: impl sqlx::PgExecutor<'_>,
+    game_result_id: uuid::Uuid,
+    username: &str,
+    counter: &StatsCounter,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE game_result_players SET hands = $3, vpip_hands = $4, pfr_hands = $5, \
+         three_bet_opportunities = $6, three_bets = $7, postflop_aggressive = $8, postflop_calls = $9, \
+         saw_flop = $10, went_to_showdown = $11, won_at_showdown = $12, total_bb = $13 \
+         WHERE game_result_id = $1 AND user_id = (SELECT id FROM users WHERE username = $2)",
+    )
+    .bind(game_result_id)
+    .bind(username)
+    .bind(counter.hands as i32)
+    .bind(counter.vpip_hands as i32)
+    .bind(counter.pfr_hands as i32)
+    .bind(counter.three_bet_opportunities as i32)
+    .bind(counter.three_bets as i32)
+    .bind(counter.postflop_aggressive as i32)
+    .bind(counter.postflop_calls as i32)
+    .bind(counter.saw_flop as i32)
+    .bind(counter.went_to_showdown as i32)
+    .bind(counter.won_at_showdown as i32)
+    .bind(counter.total_bb)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+async fn insert_hand_result(
+    db_pool: &PgPool,
+    room_id: uuid::Uuid,
+    result: &HandResult,
+) -> Result<(), sqlx::Error> {
+    let mut tx = db_pool.begin().await?;
+    let game_result_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO game_results (room_id, pot, winning_hand_rank, history) VALUES ($1, $2, $3, $4) RETURNING id",
+    )
+    .bind(room_id)
+    .bind(result.pot as i32)
+    .bind(&result.winning_hand_rank)
+    .bind(sqlx::types::Json(&result.history))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for player in &result.players {
+        sqlx::query(
+            "INSERT INTO game_result_players (game_result_id, user_id, net_chips, hand_rank, is_winner) \
+             SELECT $1, id, $3, $4, $5 FROM users WHERE username = $2",
+        )
+        .bind(game_result_id)
+        .bind(&player.username)
+        .bind(player.net_chips as i32)
+        .bind(&player.hand_rank)
+        .bind(player.is_winner)
+        .execute(&mut *tx)
+        .await?;
+        let counter = StatsCounter::for_hand(&player.username, &result.history, player.net_chips);
+        save_hand_stats(&mut *tx, game_result_id, &player.username, &counter).await?;
+    }
+    tx.commit().await
+}
+
+// トーナメントの最終順位を保存し、ルームを終了状態にする。
+// ゲームのロックを持ったまま呼ばれるので、DBへの書き込みは別タスクで行う
+fn save_tournament_results(
+    state: &AppState,
+    room_id: uuid::Uuid,
+    finishing_places: Vec<FinishingPlace>,
+) {
+    let db_pool = state.db_pool.clone();
+    tokio::spawn(async move {
+        if let Err(e) = insert_tournament_results(&db_pool, room_id, &finishing_places).await {
+            eprintln!("Failed to save tournament results: {}", e);
+        }
+    });
+}
+
+async fn insert_tournament_results(
+    db_pool: &PgPool,
+    room_id: uuid::Uuid,
+    finishing_places: &[FinishingPlace],
+) -> Result<(), sqlx::Error> {
+    let mut tx = db_pool.begin().await?;
+    sqlx::query("UPDATE rooms SET status = 'finished' WHERE id = $1")
+        .bind(room_id)
+        .execute(&mut *tx)
+        .await?;
+    for finishing_place in finishing_places {
+        sqlx::query(
+            "INSERT INTO tournament_results (room_id, user_id, place) \
+             SELECT $1, id, $3 FROM users WHERE username = $2",
+        )
+        .bind(room_id)
+        .bind(&finishing_place.username)
+        .bind(finishing_place.place as i32)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
 //registerハンドラ
 async fn register(
     State(state): State<Arc<AppState>>,
@@ -359,10 +860,10 @@ async fn register(
         Ok(_) => Ok(StatusCode::CREATED),
         Err(e) => {
             eprintln!("Failed to execute query: {}", e);
-            if let Some(db_err) = e.as_database_error() {
-                if db_err.is_unique_violation() {
-                    return Err((StatusCode::CONFLICT, "Username already exists".to_string()));
-                }
+            if let Some(db_err) = e.as_database_error()
+                && db_err.is_unique_violation()
+            {
+                return Err((StatusCode::CONFLICT, "Username already exists".to_string()));
             }
             Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -472,12 +973,29 @@ async fn create_room(
         )
     })?;
 
+    let config = payload.config;
+    config
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
     // rooms テーブルに新しいルームを挿入
     let room = sqlx::query_as::<_, Room>(
-        "INSERT INTO rooms (name, created_by) VALUES ($1, $2) RETURNING *",
+        "INSERT INTO rooms (name, created_by, small_blind, big_blind, ante, starting_stack, max_seats, min_players, \
+         action_timeout_secs, time_bank_secs, spectator_delay_secs, tournament) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) RETURNING *",
     )
     .bind(payload.name)
     .bind(user.id) // 取得した user.id を使う
+    .bind(config.small_blind as i32)
+    .bind(config.big_blind as i32)
+    .bind(config.ante as i32)
+    .bind(config.starting_stack as i32)
+    .bind(config.max_seats as i32)
+    .bind(config.min_players as i32)
+    .bind(config.action_timeout_secs as i32)
+    .bind(config.time_bank_secs as i32)
+    .bind(config.spectator_delay_secs as i32)
+    .bind(config.tournament.map(sqlx::types::Json))
     .fetch_one(&state.db_pool)
     .await
     .map_err(|e| {
@@ -494,7 +1012,7 @@ async fn create_room(
 async fn get_rooms(
     State(state): State<Arc<AppState>>,
     _claims: Claims, // ログインしているユーザーのみアクセス可能にするため
-) -> Result<Json<Vec<Room>>, (StatusCode, String)> {
+) -> Result<Json<Vec<RoomInfo>>, (StatusCode, String)> {
     let rooms = sqlx::query_as::<_, Room>("SELECT * FROM rooms ORDER BY created_at DESC")
         .fetch_all(&state.db_pool)
         .await
@@ -505,6 +1023,13 @@ async fn get_rooms(
             )
         })?;
 
+    let rooms = rooms
+        .into_iter()
+        .map(|room| RoomInfo {
+            spectator_count: state.sessions.spectator_count(room.id),
+            room,
+        })
+        .collect();
     Ok(Json(rooms))
 }
 
@@ -513,7 +1038,7 @@ async fn get_room_by_id(
     State(state): State<Arc<AppState>>,
     Path(room_id): Path<uuid::Uuid>, // ★ URLパスからroom_idを取得
     _claims: Claims,                 // 認証が必要
-) -> Result<Json<Room>, (StatusCode, String)> {
+) -> Result<Json<RoomInfo>, (StatusCode, String)> {
     let room = sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1")
         .bind(room_id)
         .fetch_optional(&state.db_pool)
@@ -526,11 +1051,237 @@ async fn get_room_by_id(
         })?;
 
     match room {
-        Some(room) => Ok(Json(room)),
+        Some(room) => Ok(Json(RoomInfo {
+            spectator_count: state.sessions.spectator_count(room.id),
+            room,
+        })),
         None => Err((StatusCode::NOT_FOUND, "Room not found".to_string())),
     }
 }
 
+// get_hand_by_idハンドラ
+async fn get_hand_by_id(
+    State(state): State<Arc<AppState>>,
+    Path(hand_id): Path<uuid::Uuid>,
+    claims: Claims,
+) -> Result<Json<HandReplay>, (StatusCode, String)> {
+    let hand = sqlx::query_as::<_, HandReplay>(
+        "SELECT id, room_id, finished_at, history FROM game_results WHERE id = $1 AND history IS NOT NULL",
+    )
+    .bind(hand_id)
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to fetch hand: {}", e),
+        )
+    })?;
+
+    match hand {
+        // 他のプレイヤーが見せなかった手札は返さない
+        Some(mut hand) => {
+            hand.history.0 = hand.history.0.visible_to(&claims.sub);
+            Ok(Json(hand))
+        }
+        None => Err((StatusCode::NOT_FOUND, "Hand not found".to_string())),
+    }
+}
+
+// verify_shuffleハンドラ
+// 公開されたシードからデッキを作り直す。誰でも検証できるようにログインは不要
+async fn verify_shuffle(
+    Json(proof): Json<ShuffleProof>,
+) -> Result<Json<VerifiedShuffle>, (StatusCode, String)> {
+    let deck = fairness::verify(&proof).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(VerifiedShuffle {
+        cards: deck.dealing_order(),
+    }))
+}
+
+// export_hand_historiesハンドラ
+// ルームで自分が参加したハンドを、PokerStars形式のテキストファイルとしてダウンロードする
+async fn export_hand_histories(
+    State(state): State<Arc<AppState>>,
+    Path(room_id): Path<uuid::Uuid>,
+    claims: Claims,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let room = sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1")
+        .bind(room_id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to fetch room: {}", e),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Room not found".to_string()))?;
+
+    let hands: Vec<(i64, sqlx::types::Json<HandHistory>)> = sqlx::query_as(
+        "SELECT gr.hand_number, gr.history FROM game_results gr \
+         WHERE gr.room_id = $1 AND gr.history IS NOT NULL AND EXISTS ( \
+             SELECT 1 FROM game_result_players p JOIN users u ON u.id = p.user_id \
+             WHERE p.game_result_id = gr.id AND u.username = $2) \
+         ORDER BY gr.finished_at, gr.hand_number",
+    )
+    .bind(room_id)
+    .bind(&claims.sub)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to fetch hands: {}", e),
+        )
+    })?;
+
+    // ハンドの間は空行で区切る
+    let text = hands
+        .iter()
+        .map(|(hand_number, history)| {
+            pokerstars::format_hand(
+                *hand_number,
+                &room.name,
+                room.max_seats as usize,
+                &claims.sub,
+                history,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let disposition = format!("attachment; filename=\"hands-{}.txt\"", room_id);
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "text/plain; charset=utf-8".to_string(),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        text,
+    ))
+}
+
+// get_user_statsハンドラ
+async fn get_user_stats(
+    State(state): State<Arc<AppState>>,
+    Path(username): Path<String>,
+    _claims: Claims, // 認証が必要
+) -> Result<Json<PlayerStats>, (StatusCode, String)> {
+    let exists: Option<uuid::Uuid> = sqlx::query_scalar("SELECT id FROM users WHERE username = $1")
+        .bind(&username)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to find user: {}", e),
+            )
+        })?;
+    if exists.is_none() {
+        return Err((StatusCode::NOT_FOUND, "User not found".to_string()));
+    }
+
+    let stats = load_player_stats(&state.db_pool, &username)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to fetch stats: {}", e),
+            )
+        })?;
+    Ok(Json(stats))
+}
+
+// get_my_historyハンドラ
+async fn get_my_history(
+    State(state): State<Arc<AppState>>,
+    claims: Claims,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<HistoryPage>, (StatusCode, String)> {
+    fetch_history(&state, &claims.sub, query).await.map(Json)
+}
+
+// get_user_historyハンドラ
+async fn get_user_history(
+    State(state): State<Arc<AppState>>,
+    Path(username): Path<String>,
+    _claims: Claims, // 認証が必要
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<HistoryPage>, (StatusCode, String)> {
+    fetch_history(&state, &username, query).await.map(Json)
+}
+
+// 指定ユーザーの戦績を新しい順に取得する
+async fn fetch_history(
+    state: &AppState,
+    username: &str,
+    query: HistoryQuery,
+) -> Result<HistoryPage, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let user_id: Option<uuid::Uuid> =
+        sqlx::query_scalar("SELECT id FROM users WHERE username = $1")
+            .bind(username)
+            .fetch_optional(&state.db_pool)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to find user: {}", e),
+                )
+            })?;
+    let Some(user_id) = user_id else {
+        return Err((StatusCode::NOT_FOUND, "User not found".to_string()));
+    };
+
+    let total: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM game_result_players WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(&state.db_pool)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to fetch history: {}", e),
+                )
+            })?;
+
+    let results = sqlx::query_as::<_, HistoryEntry>(
+        "SELECT gr.id AS game_result_id, gr.room_id, r.name AS room_name, gr.pot, gr.winning_hand_rank, \
+         gr.finished_at, grp.net_chips, grp.hand_rank, grp.is_winner, \
+         ARRAY(SELECT u.username FROM game_result_players p JOIN users u ON u.id = p.user_id \
+               WHERE p.game_result_id = gr.id ORDER BY u.username) AS participants \
+         FROM game_result_players grp \
+         JOIN game_results gr ON gr.id = grp.game_result_id \
+         JOIN rooms r ON r.id = gr.room_id \
+         WHERE grp.user_id = $1 \
+         ORDER BY gr.finished_at DESC, gr.id \
+         LIMIT $2 OFFSET $3",
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to fetch history: {}", e),
+        )
+    })?;
+
+    Ok(HistoryPage {
+        results,
+        total,
+        limit,
+        offset,
+    })
+}
+
 // get_meハンドラ
 async fn get_me(claims: Claims) -> Json<Claims> {
     Json(claims)
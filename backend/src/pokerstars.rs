@@ -0,0 +1,592 @@
+use crate::game::GamePhase;
+use crate::hand_evaluator::{self, Card, HandRank, Rank};
+use crate::hand_history::{ActionKind, HandEvent, HandHistory};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// 記録したハンドをPokerStars形式のテキストに変換する。
+// トラッキングソフトに取り込めるよう、手札は hero（ダウンロードしたユーザー）の分と
+// ショーダウンで公開された分だけを出力する
+pub fn format_hand(
+    hand_number: i64,
+    table_name: &str,
+    max_seats: usize,
+    hero: &str,
+    history: &HandHistory,
+) -> String {
+    let mut out = String::new();
+    let seat_number = |username: &str| {
+        history
+            .seats
+            .iter()
+            .find(|s| s.username == username)
+            .map_or(0, |s| s.seat + 1)
+    };
+
+    let _ = writeln!(
+        out,
+        "PokerStars Hand #{}:  Hold'em No Limit ({}/{}) - {} UTC",
+        hand_number,
+        history.small_blind,
+        history.big_blind,
+        history.started_at.format("%Y/%m/%d %H:%M:%S")
+    );
+    let _ = writeln!(
+        out,
+        "Table '{}' {}-max Seat #{} is the button",
+        table_name,
+        max_seats,
+        history
+            .button_seat
+            .map_or_else(|| seat_number(&history.dealer), |seat| seat + 1)
+    );
+    for seat in &history.seats {
+        let _ = writeln!(
+            out,
+            "Seat {}: {} ({} in chips)",
+            seat.seat + 1,
+            seat.username,
+            seat.stack
+        );
+    }
+
+    let mut small_blind: Option<&str> = None;
+    let mut big_blind: Option<&str> = None;
+    let mut hole_cards_shown = false;
+    let mut showdown_started = false;
+    let mut street = GamePhase::PreFlop;
+    let mut board: Vec<Card> = Vec::new();
+
+    let mut total_committed: u32 = 0;
+    let mut voluntary: HashMap<&str, u32> = HashMap::new(); // アンティ以外に出した額
+    let mut total_uncalled: u32 = 0;
+    let mut collected: Vec<(&str, u32)> = Vec::new();
+    let mut folded_on: HashMap<&str, GamePhase> = HashMap::new();
+    let mut showdown: HashMap<&str, (&[Card], String)> = HashMap::new();
+    let mut actions = history.classified_actions().into_iter();
+
+    for event in &history.events {
+        let is_posting = matches!(
+            event,
+            HandEvent::PostAnte { .. }
+                | HandEvent::PostSmallBlind { .. }
+                | HandEvent::PostBigBlind { .. }
+                | HandEvent::PostMissedBlinds { .. }
+                | HandEvent::DealHoleCards { .. }
+        );
+        if !is_posting && !hole_cards_shown {
+            hole_cards_shown = true;
+            let _ = writeln!(out, "*** HOLE CARDS ***");
+            if let Some(cards) = history.events.iter().find_map(|e| match e {
+                HandEvent::DealHoleCards { username, cards } if username == hero => Some(cards),
+                _ => None,
+            }) {
+                let _ = writeln!(out, "Dealt to {} [{}]", hero, format_cards(cards));
+            }
+        }
+
+        match event {
+            HandEvent::DealHoleCards { .. } => {}
+            HandEvent::PostAnte { username, amount } => {
+                total_committed += amount;
+                let _ = writeln!(out, "{}: posts the ante {}", username, amount);
+            }
+            HandEvent::PostSmallBlind { username, amount } => {
+                small_blind = Some(username);
+                total_committed += amount;
+                *voluntary.entry(username).or_default() += amount;
+                let _ = writeln!(out, "{}: posts small blind {}", username, amount);
+            }
+            HandEvent::PostBigBlind { username, amount } => {
+                big_blind = Some(username);
+                total_committed += amount;
+                *voluntary.entry(username).or_default() += amount;
+                let _ = writeln!(out, "{}: posts big blind {}", username, amount);
+            }
+            HandEvent::PostMissedBlinds {
+                username,
+                live,
+                dead,
+            } => {
+                total_committed += live + dead;
+                *voluntary.entry(username).or_default() += live + dead;
+                let line = match (*live > 0, *dead > 0) {
+                    (true, true) => format!("posts small & big blinds {}", live + dead),
+                    (true, false) => format!("posts big blind {}", live),
+                    _ => format!("posts small blind {}", dead),
+                };
+                let _ = writeln!(out, "{}: {}", username, line);
+            }
+            HandEvent::PlayerAction {
+                username, chips, ..
+            } => {
+                let Some(action) = actions.next() else {
+                    continue;
+                };
+                total_committed += chips;
+                *voluntary.entry(username).or_default() += chips;
+
+                let line = match action.kind {
+                    ActionKind::Fold => {
+                        folded_on.insert(username, street);
+                        "folds".to_string()
+                    }
+                    ActionKind::Check => "checks".to_string(),
+                    ActionKind::Call => format!("calls {}", action.chips),
+                    ActionKind::Bet => format!("bets {}", action.chips),
+                    ActionKind::Raise => {
+                        format!("raises {} to {}", action.to - action.facing, action.to)
+                    }
+                };
+                let all_in = if action.is_all_in && action.chips > 0 {
+                    " and is all-in"
+                } else {
+                    ""
+                };
+                let _ = writeln!(out, "{}: {}{}", username, line, all_in);
+            }
+            HandEvent::DealBoard {
+                street: next_street,
+                cards,
+            } => {
+                let name = match next_street {
+                    GamePhase::Flop => "FLOP",
+                    GamePhase::Turn => "TURN",
+                    _ => "RIVER",
+                };
+                if board.is_empty() {
+                    let _ = writeln!(out, "*** {} *** [{}]", name, format_cards(cards));
+                } else {
+                    let _ = writeln!(
+                        out,
+                        "*** {} *** [{}] [{}]",
+                        name,
+                        format_cards(&board),
+                        format_cards(cards)
+                    );
+                }
+                board.extend_from_slice(cards);
+                street = *next_street;
+            }
+            HandEvent::Showdown {
+                username, cards, ..
+            } => {
+                if !showdown_started {
+                    showdown_started = true;
+                    let _ = writeln!(out, "*** SHOW DOWN ***");
+                }
+                let mut seven_cards = history.board.clone();
+                seven_cards.extend_from_slice(cards);
+                let description = hand_evaluator::evaluate_hand(&seven_cards)
+                    .map(|rank| describe_hand(&rank))
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    out,
+                    "{}: shows [{}] ({})",
+                    username,
+                    format_cards(cards),
+                    description
+                );
+                showdown.insert(username, (cards, description));
+            }
+            HandEvent::MuckCards { username } => {
+                if !showdown_started {
+                    showdown_started = true;
+                    let _ = writeln!(out, "*** SHOW DOWN ***");
+                }
+                let _ = writeln!(out, "{}: mucks hand", username);
+            }
+            HandEvent::ReturnUncalledBet { username, amount } => {
+                total_uncalled += amount;
+                let _ = writeln!(out, "Uncalled bet ({}) returned to {}", amount, username);
+            }
+            HandEvent::CollectPot {
+                username, amount, ..
+            } => match collected.iter_mut().find(|(u, _)| u == username) {
+                Some((_, total)) => *total += amount,
+                None => collected.push((username, *amount)),
+            },
+        }
+    }
+    for (username, amount) in &collected {
+        let _ = writeln!(out, "{} collected {} from pot", username, amount);
+    }
+
+    let _ = writeln!(out, "*** SUMMARY ***");
+    let _ = writeln!(
+        out,
+        "Total pot {} | Rake 0",
+        total_committed - total_uncalled
+    );
+    if !history.board.is_empty() {
+        let _ = writeln!(out, "Board [{}]", format_cards(&history.board));
+    }
+    for seat in &history.seats {
+        let username = seat.username.as_str();
+        let position = if username == history.dealer {
+            " (button)"
+        } else if Some(username) == small_blind {
+            " (small blind)"
+        } else if Some(username) == big_blind {
+            " (big blind)"
+        } else {
+            ""
+        };
+        let won = collected
+            .iter()
+            .find(|(u, _)| *u == username)
+            .map(|(_, amount)| *amount);
+        let result = if let Some(folded_street) = folded_on.get(username) {
+            let street_name = match folded_street {
+                GamePhase::PreFlop => "before Flop".to_string(),
+                other => format!("on the {:?}", other),
+            };
+            let didnt_bet = *folded_street == GamePhase::PreFlop
+                && voluntary.get(username).copied().unwrap_or(0) == 0;
+            format!(
+                "folded {}{}",
+                street_name,
+                if didnt_bet { " (didn't bet)" } else { "" }
+            )
+        } else if let Some((cards, description)) = showdown.get(username) {
+            match won {
+                Some(amount) if amount > 0 => format!(
+                    "showed [{}] and won ({}) with {}",
+                    format_cards(cards),
+                    amount,
+                    description
+                ),
+                _ => format!(
+                    "showed [{}] and lost with {}",
+                    format_cards(cards),
+                    description
+                ),
+            }
+        } else if let Some(amount) = won {
+            format!("collected ({})", amount)
+        } else {
+            "mucked".to_string()
+        };
+        let _ = writeln!(
+            out,
+            "Seat {}: {}{} {}",
+            seat.seat + 1,
+            username,
+            position,
+            result
+        );
+    }
+    out
+}
+
+// PokerStars形式のカード表記（例: "Ks Td"）
+fn format_cards(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(|card| {
+            let text = card.to_string();
+            let (rank, suit) = text.split_at(1);
+            format!("{}{}", rank, suit.to_lowercase())
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn rank_name(rank: Rank) -> &'static str {
+    match rank {
+        Rank::Two => "Deuce",
+        Rank::Three => "Three",
+        Rank::Four => "Four",
+        Rank::Five => "Five",
+        Rank::Six => "Six",
+        Rank::Seven => "Seven",
+        Rank::Eight => "Eight",
+        Rank::Nine => "Nine",
+        Rank::Ten => "Ten",
+        Rank::Jack => "Jack",
+        Rank::Queen => "Queen",
+        Rank::King => "King",
+        Rank::Ace => "Ace",
+    }
+}
+
+fn rank_plural(rank: Rank) -> String {
+    match rank {
+        Rank::Six => "Sixes".to_string(),
+        other => format!("{}s", rank_name(other)),
+    }
+}
+
+// PokerStars形式の役の説明（例: "a pair of Kings"）
+fn describe_hand(rank: &HandRank) -> String {
+    match *rank {
+        HandRank::HighCard(high, ..) => format!("high card {}", rank_name(high)),
+        HandRank::OnePair(pair, ..) => format!("a pair of {}", rank_plural(pair)),
+        HandRank::TwoPair(high, low, _) => {
+            format!("two pair, {} and {}", rank_plural(high), rank_plural(low))
+        }
+        HandRank::ThreeOfAKind(trips, ..) => {
+            format!("three of a kind, {}", rank_plural(trips))
+        }
+        HandRank::Straight(high) => format!(
+            "a straight, {} to {}",
+            rank_name(straight_low(high)),
+            rank_name(high)
+        ),
+        HandRank::Flush(high, ..) => format!("a flush, {} high", rank_name(high)),
+        HandRank::FullHouse(trips, pair) => format!(
+            "a full house, {} full of {}",
+            rank_plural(trips),
+            rank_plural(pair)
+        ),
+        HandRank::FourOfAKind(quads, _) => format!("four of a kind, {}", rank_plural(quads)),
+        HandRank::StraightFlush(high) => format!(
+            "a straight flush, {} to {}",
+            rank_name(straight_low(high)),
+            rank_name(high)
+        ),
+        HandRank::RoyalFlush => "a Royal Flush".to_string(),
+    }
+}
+
+// ストレートの一番下のカード。5ハイ（ホイール）はAから
+fn straight_low(high: Rank) -> Rank {
+    match Rank::ALL.iter().position(|r| *r == high) {
+        Some(index) if index >= 4 => Rank::ALL[index - 4],
+        _ => Rank::Ace,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PlayerAction;
+    use crate::hand_history::Seat;
+    use chrono::{TimeZone, Utc};
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_whitespace().map(|c| c.parse().unwrap()).collect()
+    }
+
+    fn action(street: GamePhase, username: &str, action: PlayerAction, chips: u32) -> HandEvent {
+        HandEvent::PlayerAction {
+            street,
+            username: username.to_string(),
+            action,
+            chips,
+            is_all_in: false,
+        }
+    }
+
+    fn returned(username: &str, amount: u32) -> HandEvent {
+        HandEvent::ReturnUncalledBet {
+            username: username.to_string(),
+            amount,
+        }
+    }
+
+    fn collect(username: &str, amount: u32) -> HandEvent {
+        HandEvent::CollectPot {
+            username: username.to_string(),
+            amount,
+            pot_index: 0,
+        }
+    }
+
+    // alice（ボタン）、bob（SB）、carol（BB）の3人でブラインドを払い、手札が配られたところ
+    fn history(board: &str, events: Vec<HandEvent>) -> HandHistory {
+        let mut all_events = vec![
+            HandEvent::PostSmallBlind {
+                username: "bob".to_string(),
+                amount: 10,
+            },
+            HandEvent::PostBigBlind {
+                username: "carol".to_string(),
+                amount: 20,
+            },
+        ];
+        for (username, hand) in [("alice", "AH KD"), ("bob", "7S 2C"), ("carol", "QC JC")] {
+            all_events.push(HandEvent::DealHoleCards {
+                username: username.to_string(),
+                cards: cards(hand),
+            });
+        }
+        all_events.extend(events);
+        HandHistory {
+            started_at: Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap(),
+            dealer: "alice".to_string(),
+            button_seat: Some(0),
+            small_blind: 10,
+            big_blind: 20,
+            ante: 0,
+            seed: None,
+            shuffle: None,
+            seats: ["alice", "bob", "carol"]
+                .into_iter()
+                .enumerate()
+                .map(|(seat, username)| Seat {
+                    seat,
+                    username: username.to_string(),
+                    stack: 1000,
+                })
+                .collect(),
+            board: cards(board),
+            events: all_events,
+        }
+    }
+
+    fn board(street: GamePhase, s: &str) -> HandEvent {
+        HandEvent::DealBoard {
+            street,
+            cards: cards(s),
+        }
+    }
+
+    #[test]
+    fn a_hand_folded_to_the_big_blind_returns_the_uncalled_blind() {
+        use GamePhase::*;
+        let fold = history(
+            "",
+            vec![
+                action(PreFlop, "alice", PlayerAction::Fold, 0),
+                action(PreFlop, "bob", PlayerAction::Fold, 0),
+                returned("carol", 10),
+                collect("carol", 20),
+            ],
+        );
+        let expected = "\
+PokerStars Hand #42:  Hold'em No Limit (10/20) - 2026/10/17 12:00:00 UTC
+Table 'Table' 6-max Seat #1 is the button
+Seat 1: alice (1000 in chips)
+Seat 2: bob (1000 in chips)
+Seat 3: carol (1000 in chips)
+bob: posts small blind 10
+carol: posts big blind 20
+*** HOLE CARDS ***
+Dealt to alice [Ah Kd]
+alice: folds
+bob: folds
+Uncalled bet (10) returned to carol
+carol collected 20 from pot
+*** SUMMARY ***
+Total pot 20 | Rake 0
+Seat 1: alice (button) folded before Flop (didn't bet)
+Seat 2: bob (small blind) folded before Flop
+Seat 3: carol (big blind) collected (20)
+";
+        assert_eq!(format_hand(42, "Table", 6, "alice", &fold), expected);
+    }
+
+    #[test]
+    fn a_showdown_hand_lists_shown_and_mucked_hands() {
+        use GamePhase::*;
+        let showdown = history(
+            "KS 7C 2D 9H 3S",
+            vec![
+                action(PreFlop, "alice", PlayerAction::Raise { to: 60 }, 60),
+                action(PreFlop, "bob", PlayerAction::Fold, 0),
+                action(PreFlop, "carol", PlayerAction::Call, 40),
+                board(Flop, "KS 7C 2D"),
+                action(Flop, "carol", PlayerAction::Check, 0),
+                action(Flop, "alice", PlayerAction::Bet { amount: 100 }, 100),
+                action(Flop, "carol", PlayerAction::Call, 100),
+                board(Turn, "9H"),
+                action(Turn, "carol", PlayerAction::Check, 0),
+                action(Turn, "alice", PlayerAction::Check, 0),
+                board(River, "3S"),
+                action(River, "carol", PlayerAction::Check, 0),
+                action(River, "alice", PlayerAction::Check, 0),
+                HandEvent::Showdown {
+                    username: "alice".to_string(),
+                    cards: cards("AH KD"),
+                    hand_rank: "ワンペア".to_string(),
+                },
+                HandEvent::MuckCards {
+                    username: "carol".to_string(),
+                },
+                collect("alice", 330),
+            ],
+        );
+        let expected = "\
+PokerStars Hand #42:  Hold'em No Limit (10/20) - 2026/10/17 12:00:00 UTC
+Table 'Table' 6-max Seat #1 is the button
+Seat 1: alice (1000 in chips)
+Seat 2: bob (1000 in chips)
+Seat 3: carol (1000 in chips)
+bob: posts small blind 10
+carol: posts big blind 20
+*** HOLE CARDS ***
+Dealt to alice [Ah Kd]
+alice: raises 40 to 60
+bob: folds
+carol: calls 40
+*** FLOP *** [Ks 7c 2d]
+carol: checks
+alice: bets 100
+carol: calls 100
+*** TURN *** [Ks 7c 2d] [9h]
+carol: checks
+alice: checks
+*** RIVER *** [Ks 7c 2d 9h] [3s]
+carol: checks
+alice: checks
+*** SHOW DOWN ***
+alice: shows [Ah Kd] (a pair of Kings)
+carol: mucks hand
+alice collected 330 from pot
+*** SUMMARY ***
+Total pot 330 | Rake 0
+Board [Ks 7c 2d 9h 3s]
+Seat 1: alice (button) showed [Ah Kd] and won (330) with a pair of Kings
+Seat 2: bob (small blind) folded before Flop
+Seat 3: carol (big blind) mucked
+";
+        assert_eq!(format_hand(42, "Table", 6, "alice", &showdown), expected);
+    }
+
+    #[test]
+    fn an_uncalled_bet_is_returned_before_collecting_the_pot() {
+        use GamePhase::*;
+        let uncalled = history(
+            "KS 7C 2D",
+            vec![
+                action(PreFlop, "alice", PlayerAction::Raise { to: 60 }, 60),
+                action(PreFlop, "bob", PlayerAction::Fold, 0),
+                action(PreFlop, "carol", PlayerAction::Call, 40),
+                board(Flop, "KS 7C 2D"),
+                action(Flop, "carol", PlayerAction::Check, 0),
+                action(Flop, "alice", PlayerAction::Bet { amount: 100 }, 100),
+                action(Flop, "carol", PlayerAction::Fold, 0),
+                returned("alice", 100),
+                collect("alice", 130),
+            ],
+        );
+        let expected = "\
+PokerStars Hand #42:  Hold'em No Limit (10/20) - 2026/10/17 12:00:00 UTC
+Table 'Table' 6-max Seat #1 is the button
+Seat 1: alice (1000 in chips)
+Seat 2: bob (1000 in chips)
+Seat 3: carol (1000 in chips)
+bob: posts small blind 10
+carol: posts big blind 20
+*** HOLE CARDS ***
+Dealt to alice [Ah Kd]
+alice: raises 40 to 60
+bob: folds
+carol: calls 40
+*** FLOP *** [Ks 7c 2d]
+carol: checks
+alice: bets 100
+carol: folds
+Uncalled bet (100) returned to alice
+alice collected 130 from pot
+*** SUMMARY ***
+Total pot 130 | Rake 0
+Board [Ks 7c 2d]
+Seat 1: alice (button) collected (130)
+Seat 2: bob (small blind) folded before Flop
+Seat 3: carol (big blind) folded on the Flop
+";
+        assert_eq!(format_hand(42, "Table", 6, "alice", &uncalled), expected);
+    }
+}
@@ -0,0 +1,169 @@
+use dashmap::DashMap;
+use std::collections::HashSet;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+// 1つのWebSocket接続
+struct Session {
+    username: String,
+    sender: mpsc::Sender<String>,
+    is_spectator: bool, // 観戦者には手札や取れるアクションを送らない
+}
+
+// ルームごと・接続ごとの個別メッセージ送信先を管理する。
+// 同じユーザーが複数のルームや複数のタブから接続しても、接続ごとに区別される
+#[derive(Default)]
+pub struct SessionRegistry {
+    // room_id -> connection_id -> Session
+    rooms: DashMap<Uuid, DashMap<Uuid, Session>>,
+    // room_id -> 着席しているユーザー名。ブロードキャストを受けるたびにゲームのロックを取らずに済むよう、
+    // ゲーム状態を送るときに更新する
+    seated: DashMap<Uuid, HashSet<String>>,
+}
+
+impl SessionRegistry {
+    // 接続を登録し、接続IDを返す
+    pub fn register(
+        &self,
+        room_id: Uuid,
+        username: &str,
+        sender: mpsc::Sender<String>,
+        is_spectator: bool,
+    ) -> Uuid {
+        let connection_id = Uuid::new_v4();
+        self.rooms.entry(room_id).or_default().insert(
+            connection_id,
+            Session {
+                username: username.to_string(),
+                sender,
+                is_spectator,
+            },
+        );
+        connection_id
+    }
+
+    // このルームを観戦している接続の数
+    pub fn spectator_count(&self, room_id: Uuid) -> usize {
+        self.rooms.get(&room_id).map_or(0, |sessions| {
+            sessions.iter().filter(|s| s.is_spectator).count()
+        })
+    }
+
+    // 接続を解除する。そのユーザーがこのルームに他のプレイヤーとしての接続を持っていなければtrueを返す
+    pub fn unregister(&self, room_id: Uuid, connection_id: Uuid) -> bool {
+        let Some(sessions) = self.rooms.get(&room_id) else {
+            return true;
+        };
+        let Some((_, session)) = sessions.remove(&connection_id) else {
+            return true;
+        };
+        let is_last = !sessions
+            .iter()
+            .any(|s| s.username == session.username && !s.is_spectator);
+        let is_empty = sessions.is_empty();
+        drop(sessions);
+        if is_empty {
+            self.rooms
+                .remove_if(&room_id, |_, sessions| sessions.is_empty());
+        }
+        is_last
+    }
+
+    // このルームにいる指定ユーザーの全接続（観戦を除く）にメッセージを送る
+    pub async fn send_to_user(&self, room_id: Uuid, username: &str, message: String) {
+        // DashMapのロックをawaitをまたいで保持しないよう、送信チャネルを複製しておく
+        let senders: Vec<mpsc::Sender<String>> = match self.rooms.get(&room_id) {
+            Some(sessions) => sessions
+                .iter()
+                .filter(|s| s.username == username && !s.is_spectator)
+                .map(|s| s.sender.clone())
+                .collect(),
+            None => return,
+        };
+        for sender in senders {
+            let _ = sender.send(message.clone()).await;
+        }
+    }
+
+    // ルームに着席しているユーザーを更新する
+    pub fn set_seated(&self, room_id: Uuid, usernames: HashSet<String>) {
+        self.seated.insert(room_id, usernames);
+    }
+
+    // 指定ユーザーがこのルームに着席しているか
+    pub fn is_seated(&self, room_id: Uuid, username: &str) -> bool {
+        self.seated
+            .get(&room_id)
+            .is_some_and(|usernames| usernames.contains(username))
+    }
+
+    // 片付けたルームの着席情報を消す
+    pub fn remove_room(&self, room_id: Uuid) {
+        self.seated.remove(&room_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connect(
+        sessions: &SessionRegistry,
+        room_id: Uuid,
+        username: &str,
+        is_spectator: bool,
+    ) -> (Uuid, mpsc::Receiver<String>) {
+        let (sender, receiver) = mpsc::channel(8);
+        let connection_id = sessions.register(room_id, username, sender, is_spectator);
+        (connection_id, receiver)
+    }
+
+    #[tokio::test]
+    async fn private_messages_reach_only_the_connection_in_that_room() {
+        let sessions = SessionRegistry::default();
+        let (room_a, room_b) = (Uuid::new_v4(), Uuid::new_v4());
+        let (_, mut in_a) = connect(&sessions, room_a, "alice", false);
+        let (_, mut in_b) = connect(&sessions, room_b, "alice", false);
+
+        sessions
+            .send_to_user(room_a, "alice", "hand".to_string())
+            .await;
+        assert_eq!(in_a.try_recv().as_deref(), Ok("hand"));
+        assert!(in_b.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn every_tab_in_a_room_receives_private_messages_but_spectating_ones_do_not() {
+        let sessions = SessionRegistry::default();
+        let room_id = Uuid::new_v4();
+        let (_, mut first_tab) = connect(&sessions, room_id, "alice", false);
+        let (_, mut second_tab) = connect(&sessions, room_id, "alice", false);
+        let (_, mut spectating) = connect(&sessions, room_id, "alice", true);
+        let (_, mut bob) = connect(&sessions, room_id, "bob", false);
+
+        sessions
+            .send_to_user(room_id, "alice", "hand".to_string())
+            .await;
+        assert_eq!(first_tab.try_recv().as_deref(), Ok("hand"));
+        assert_eq!(second_tab.try_recv().as_deref(), Ok("hand"));
+        assert!(spectating.try_recv().is_err());
+        assert!(bob.try_recv().is_err());
+    }
+
+    #[test]
+    fn only_the_last_player_connection_of_a_user_is_reported_as_last() {
+        let sessions = SessionRegistry::default();
+        let (room_a, room_b) = (Uuid::new_v4(), Uuid::new_v4());
+        let (first_tab, _first) = connect(&sessions, room_a, "alice", false);
+        let (second_tab, _second) = connect(&sessions, room_a, "alice", false);
+        let (spectating, _spectating) = connect(&sessions, room_a, "alice", true);
+        let (other_room, _other) = connect(&sessions, room_b, "alice", false);
+
+        assert!(!sessions.unregister(room_a, first_tab));
+        // 観戦の接続やほかのルームの接続は、このルームのプレイヤーとしての接続に数えない
+        assert!(sessions.unregister(room_a, second_tab));
+        assert_eq!(sessions.spectator_count(room_a), 1);
+        assert!(sessions.unregister(room_a, spectating));
+        assert!(sessions.unregister(room_b, other_room));
+    }
+}
@@ -0,0 +1,347 @@
+use crate::game::GamePhase;
+use crate::hand_history::{ActionKind, HandEvent, HandHistory};
+use serde::{Deserialize, Serialize};
+
+// HUDに表示するプレイヤーの統計（割合は0〜100のパーセント）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlayerStats {
+    pub username: String,
+    pub hands: u32,
+    pub vpip: f64,                      // 自発的にプリフロップでチップを入れた割合
+    pub pfr: f64,                       // プリフロップでレイズした割合
+    pub three_bet: f64,                 // 3ベットできる場面で3ベットした割合
+    pub aggression_factor: Option<f64>, // フロップ以降の (ベット＋レイズ) / コール。コールがなければNone
+    pub wtsd: f64,                      // フロップを見たハンドのうちショーダウンまで行った割合
+    pub wsd: f64,                       // ショーダウンでポットを獲得した割合
+    pub bb_per_100: f64,                // 100ハンドあたりの獲得BB
+}
+
+// 記録されたハンドから統計を集計する。
+// 1ハンド分の集計値はハンドの結果と一緒にDBに保存し、統計はそれを合計して求める
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct StatsCounter {
+    pub hands: u32,
+    pub vpip_hands: u32,
+    pub pfr_hands: u32,
+    pub three_bet_opportunities: u32,
+    pub three_bets: u32,
+    pub postflop_aggressive: u32,
+    pub postflop_calls: u32,
+    pub saw_flop: u32,
+    pub went_to_showdown: u32,
+    pub won_at_showdown: u32,
+    pub total_bb: f64,
+}
+
+impl StatsCounter {
+    // 1ハンド分の集計値
+    pub fn for_hand(username: &str, history: &HandHistory, net_chips: i64) -> Self {
+        let mut counter = StatsCounter::default();
+        counter.add_hand(username, history, net_chips);
+        counter
+    }
+
+    // 1ハンド分を集計に加える。net_chips はそのハンドでのチップの増減
+    pub fn add_hand(&mut self, username: &str, history: &HandHistory, net_chips: i64) {
+        if !history.seats.iter().any(|s| s.username == username) {
+            return;
+        }
+        self.hands += 1;
+        if history.big_blind > 0 {
+            self.total_bb += net_chips as f64 / history.big_blind as f64;
+        }
+
+        let actions = history.classified_actions();
+        let mut voluntary = false;
+        let mut raised = false;
+        let mut folded_preflop = false;
+        let mut preflop_raises = 0;
+        let mut had_three_bet_opportunity = false;
+        for action in actions.iter().filter(|a| a.street == GamePhase::PreFlop) {
+            let is_raise = matches!(action.kind, ActionKind::Bet | ActionKind::Raise);
+            if action.username == username {
+                // 1回目のレイズに対して、初めて回ってきたアクションが3ベットの機会
+                if preflop_raises == 1 && !had_three_bet_opportunity && !raised {
+                    had_three_bet_opportunity = true;
+                    self.three_bet_opportunities += 1;
+                    if is_raise {
+                        self.three_bets += 1;
+                    }
+                }
+                match action.kind {
+                    ActionKind::Call => voluntary = true,
+                    ActionKind::Bet | ActionKind::Raise => {
+                        voluntary = true;
+                        raised = true;
+                    }
+                    ActionKind::Fold => folded_preflop = true,
+                    ActionKind::Check => {}
+                }
+            }
+            if is_raise {
+                preflop_raises += 1;
+            }
+        }
+        if voluntary {
+            self.vpip_hands += 1;
+        }
+        if raised {
+            self.pfr_hands += 1;
+        }
+
+        for action in actions
+            .iter()
+            .filter(|a| a.street != GamePhase::PreFlop && a.username == username)
+        {
+            match action.kind {
+                ActionKind::Bet | ActionKind::Raise => self.postflop_aggressive += 1,
+                ActionKind::Call => self.postflop_calls += 1,
+                _ => {}
+            }
+        }
+
+        let flop_dealt = history.events.iter().any(|e| {
+            matches!(
+                e,
+                HandEvent::DealBoard {
+                    street: GamePhase::Flop,
+                    ..
+                }
+            )
+        });
+        if !flop_dealt || folded_preflop {
+            return;
+        }
+        self.saw_flop += 1;
+
+        // 手札を見せずにマックした場合もショーダウンまで行ったことになる
+        let showed_down = history.events.iter().any(|e| match e {
+            HandEvent::Showdown { username: u, .. } | HandEvent::MuckCards { username: u } => {
+                u == username
+            }
+            _ => false,
+        });
+        if showed_down {
+            self.went_to_showdown += 1;
+            let won = history.events.iter().any(|e| match e {
+                HandEvent::CollectPot {
+                    username: u,
+                    amount,
+                    ..
+                } => u == username && *amount > 0,
+                _ => false,
+            });
+            if won {
+                self.won_at_showdown += 1;
+            }
+        }
+    }
+
+    pub fn finish(&self, username: &str) -> PlayerStats {
+        PlayerStats {
+            username: username.to_string(),
+            hands: self.hands,
+            vpip: percent(self.vpip_hands, self.hands),
+            pfr: percent(self.pfr_hands, self.hands),
+            three_bet: percent(self.three_bets, self.three_bet_opportunities),
+            aggression_factor: (self.postflop_calls > 0)
+                .then(|| self.postflop_aggressive as f64 / self.postflop_calls as f64),
+            wtsd: percent(self.went_to_showdown, self.saw_flop),
+            wsd: percent(self.won_at_showdown, self.went_to_showdown),
+            bb_per_100: if self.hands > 0 {
+                self.total_bb * 100.0 / self.hands as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+fn percent(count: u32, total: u32) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PlayerAction;
+    use crate::hand_evaluator::Card;
+    use crate::hand_history::Seat;
+    use GamePhase::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_whitespace().map(|c| c.parse().unwrap()).collect()
+    }
+
+    fn action(street: GamePhase, username: &str, action: PlayerAction, chips: u32) -> HandEvent {
+        HandEvent::PlayerAction {
+            street,
+            username: username.to_string(),
+            action,
+            chips,
+            is_all_in: false,
+        }
+    }
+
+    // alice（ボタン）、bob（SB 10）、carol（BB 20）の3人のハンド
+    fn history(events: Vec<HandEvent>) -> HandHistory {
+        let mut all_events = vec![
+            HandEvent::PostSmallBlind {
+                username: "bob".to_string(),
+                amount: 10,
+            },
+            HandEvent::PostBigBlind {
+                username: "carol".to_string(),
+                amount: 20,
+            },
+        ];
+        all_events.extend(events);
+        HandHistory {
+            dealer: "alice".to_string(),
+            button_seat: Some(0),
+            small_blind: 10,
+            big_blind: 20,
+            seats: ["alice", "bob", "carol"]
+                .into_iter()
+                .enumerate()
+                .map(|(seat, username)| Seat {
+                    seat,
+                    username: username.to_string(),
+                    stack: 1000,
+                })
+                .collect(),
+            events: all_events,
+            ..Default::default()
+        }
+    }
+
+    // aliceのオープンにbobが3ベットし、フロップでbobのベットにaliceがレイズ。
+    // ショーダウンでaliceが見せて勝ち、bobはマックする
+    fn three_bet_pot() -> HandHistory {
+        history(vec![
+            action(PreFlop, "alice", PlayerAction::Raise { to: 60 }, 60),
+            action(PreFlop, "bob", PlayerAction::Raise { to: 180 }, 170),
+            action(PreFlop, "carol", PlayerAction::Fold, 0),
+            action(PreFlop, "alice", PlayerAction::Call, 120),
+            HandEvent::DealBoard {
+                street: Flop,
+                cards: cards("KS 7C 2D"),
+            },
+            action(Flop, "bob", PlayerAction::Bet { amount: 100 }, 100),
+            action(Flop, "alice", PlayerAction::Raise { to: 300 }, 300),
+            action(Flop, "bob", PlayerAction::Call, 200),
+            HandEvent::DealBoard {
+                street: Turn,
+                cards: cards("9H"),
+            },
+            action(Turn, "bob", PlayerAction::Check, 0),
+            action(Turn, "alice", PlayerAction::Check, 0),
+            HandEvent::DealBoard {
+                street: River,
+                cards: cards("3S"),
+            },
+            action(River, "bob", PlayerAction::Check, 0),
+            action(River, "alice", PlayerAction::Check, 0),
+            HandEvent::Showdown {
+                username: "alice".to_string(),
+                cards: cards("AH KD"),
+                hand_rank: "ワンペア".to_string(),
+            },
+            HandEvent::MuckCards {
+                username: "bob".to_string(),
+            },
+            HandEvent::CollectPot {
+                username: "alice".to_string(),
+                amount: 980,
+                pot_index: 0,
+            },
+        ])
+    }
+
+    // 全員がBBにフォールドする
+    fn walk() -> HandHistory {
+        history(vec![
+            action(PreFlop, "alice", PlayerAction::Fold, 0),
+            action(PreFlop, "bob", PlayerAction::Fold, 0),
+            HandEvent::CollectPot {
+                username: "carol".to_string(),
+                amount: 30,
+                pot_index: 0,
+            },
+        ])
+    }
+
+    // 2ハンド分の統計。net_chips は (3ベットポット, ウォーク) の順
+    fn stats(username: &str, net_chips: [i64; 2]) -> PlayerStats {
+        let mut counter = StatsCounter::default();
+        counter.add_hand(username, &three_bet_pot(), net_chips[0]);
+        counter.add_hand(username, &walk(), net_chips[1]);
+        counter.finish(username)
+    }
+
+    #[test]
+    fn opener_who_wins_at_showdown() {
+        assert_eq!(
+            stats("alice", [500, 0]),
+            PlayerStats {
+                username: "alice".to_string(),
+                hands: 2,
+                vpip: 50.0,
+                pfr: 50.0,
+                three_bet: 0.0,          // 自分がオープンしたので3ベットの機会はない
+                aggression_factor: None, // コールしていない
+                wtsd: 100.0,
+                wsd: 100.0,
+                bb_per_100: 1250.0, // +25BB / 2ハンド
+            }
+        );
+    }
+
+    #[test]
+    fn three_bettor_who_mucks_at_showdown() {
+        assert_eq!(
+            stats("bob", [-480, -10]),
+            PlayerStats {
+                username: "bob".to_string(),
+                hands: 2,
+                vpip: 50.0, // SBを払っただけのハンドは数えない
+                pfr: 50.0,
+                three_bet: 100.0,
+                aggression_factor: Some(1.0), // ベット1回 / コール1回
+                wtsd: 100.0,                  // マックしてもショーダウンまで行ったことになる
+                wsd: 0.0,
+                bb_per_100: -1225.0, // -24.5BB / 2ハンド
+            }
+        );
+    }
+
+    #[test]
+    fn big_blind_who_folds_to_a_three_bet_and_wins_a_walk() {
+        assert_eq!(
+            stats("carol", [-20, 10]),
+            PlayerStats {
+                username: "carol".to_string(),
+                hands: 2,
+                vpip: 0.0,
+                pfr: 0.0,
+                three_bet: 0.0, // 3ベットの後に回ってきたのは機会に数えない
+                aggression_factor: None,
+                wtsd: 0.0, // フロップを見ていない
+                wsd: 0.0,
+                bb_per_100: -25.0,
+            }
+        );
+    }
+
+    #[test]
+    fn hands_the_player_did_not_sit_in_are_ignored() {
+        assert_eq!(
+            StatsCounter::for_hand("dave", &three_bet_pot(), 0),
+            StatsCounter::default()
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// 1レベル分のブラインド・アンティ
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BlindLevel {
+    pub small_blind: u32,
+    pub big_blind: u32,
+    #[serde(default)]
+    pub ante: u32,
+}
+
+// ブラインドレベルを上げるタイミング
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "type")]
+pub enum LevelAdvance {
+    Hands { hands_per_level: u32 },
+    Minutes { minutes_per_level: u32 },
+}
+
+// トーナメントの設定（ルーム作成時に指定する）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TournamentConfig {
+    pub levels: Vec<BlindLevel>,
+    pub advance: LevelAdvance,
+}
+
+impl TournamentConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.levels.is_empty() {
+            return Err("Tournament needs at least one blind level".to_string());
+        }
+        for level in &self.levels {
+            if level.big_blind == 0 || level.small_blind > level.big_blind {
+                return Err("Small blind must not exceed a non-zero big blind".to_string());
+            }
+        }
+        match self.advance {
+            LevelAdvance::Hands { hands_per_level: 0 }
+            | LevelAdvance::Minutes {
+                minutes_per_level: 0,
+            } => Err("Level duration must be positive".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+// 敗退したプレイヤーの順位
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FinishingPlace {
+    pub username: String,
+    pub place: usize,
+}
+
+// トーナメントの進行状況
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TournamentState {
+    pub config: TournamentConfig,
+    pub level_index: usize,
+    pub hands_played_in_level: u32,
+    pub level_started_at: Option<DateTime<Utc>>, // 最初のハンド開始時に記録
+    pub finishing_places: Vec<FinishingPlace>,   // 敗退順ではなく順位の昇順
+    pub is_finished: bool,
+}
+
+impl TournamentState {
+    pub fn new(config: TournamentConfig) -> Self {
+        TournamentState {
+            config,
+            level_index: 0,
+            hands_played_in_level: 0,
+            level_started_at: None,
+            finishing_places: Vec::new(),
+            is_finished: false,
+        }
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.level_started_at.is_some()
+    }
+
+    pub fn current_level(&self) -> BlindLevel {
+        self.config.levels[self.level_index]
+    }
+
+    // 次のハンドを始める前に、必要ならブラインドレベルを上げる
+    pub fn update_level(&mut self, now: DateTime<Utc>) {
+        let Some(started_at) = self.level_started_at else {
+            self.level_started_at = Some(now);
+            return;
+        };
+        let last_level = self.config.levels.len() - 1;
+        match self.config.advance {
+            LevelAdvance::Hands { hands_per_level } => {
+                if self.hands_played_in_level >= hands_per_level && self.level_index < last_level {
+                    self.level_index += 1;
+                    self.hands_played_in_level = 0;
+                    self.level_started_at = Some(now);
+                }
+            }
+            LevelAdvance::Minutes { minutes_per_level } => {
+                // 長いハンドの間に複数レベル分の時間が経過することもある
+                let level_duration = chrono::Duration::minutes(minutes_per_level as i64);
+                let mut level_started_at = started_at;
+                while now - level_started_at >= level_duration && self.level_index < last_level {
+                    self.level_index += 1;
+                    self.hands_played_in_level = 0;
+                    level_started_at += level_duration;
+                }
+                self.level_started_at = Some(level_started_at);
+            }
+        }
+    }
+
+    // ハンド終了時に呼ぶ。busted は (ユーザー名, ハンド開始時のスタック)
+    // remaining はまだチップを持っているプレイヤーの数
+    pub fn record_hand(&mut self, mut busted: Vec<(String, u32)>, remaining: usize) {
+        self.hands_played_in_level += 1;
+
+        // 同じハンドで複数人が飛んだ場合は、開始時のスタックが多い方が上位
+        busted.sort_by_key(|b| std::cmp::Reverse(b.1));
+        for (i, (username, _)) in busted.into_iter().enumerate() {
+            self.finishing_places.push(FinishingPlace {
+                username,
+                place: remaining + i + 1,
+            });
+        }
+        self.finishing_places.sort_by_key(|p| p.place);
+    }
+
+    // 優勝者を記録してトーナメントを終了する
+    pub fn finish(&mut self, winner: String) {
+        self.finishing_places.insert(
+            0,
+            FinishingPlace {
+                username: winner,
+                place: 1,
+            },
+        );
+        self.is_finished = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn config(advance: LevelAdvance) -> TournamentConfig {
+        TournamentConfig {
+            levels: [(10, 20), (20, 40), (50, 100)]
+                .into_iter()
+                .map(|(small_blind, big_blind)| BlindLevel {
+                    small_blind,
+                    big_blind,
+                    ante: 0,
+                })
+                .collect(),
+            advance,
+        }
+    }
+
+    #[test]
+    fn validate_rejects_empty_levels_bad_blinds_and_zero_durations() {
+        let hands = LevelAdvance::Hands { hands_per_level: 5 };
+        assert!(config(hands).validate().is_ok());
+
+        let mut empty = config(hands);
+        empty.levels.clear();
+        assert!(empty.validate().is_err());
+
+        let mut inverted = config(hands);
+        inverted.levels[1].small_blind = 50;
+        assert!(inverted.validate().is_err());
+
+        let mut no_big_blind = config(hands);
+        no_big_blind.levels[2].big_blind = 0;
+        assert!(no_big_blind.validate().is_err());
+
+        assert!(
+            config(LevelAdvance::Hands { hands_per_level: 0 })
+                .validate()
+                .is_err()
+        );
+        assert!(
+            config(LevelAdvance::Minutes {
+                minutes_per_level: 0
+            })
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn levels_advance_by_hands_and_stop_at_the_last_level() {
+        let mut tournament =
+            TournamentState::new(config(LevelAdvance::Hands { hands_per_level: 2 }));
+        let now = Utc::now();
+        let mut levels = Vec::new();
+        for _ in 0..7 {
+            tournament.update_level(now);
+            levels.push(tournament.current_level().big_blind);
+            tournament.record_hand(Vec::new(), 3);
+        }
+        assert_eq!(levels, vec![20, 20, 40, 40, 100, 100, 100]);
+    }
+
+    #[test]
+    fn levels_advance_by_minutes_even_across_a_long_hand() {
+        let mut tournament = TournamentState::new(config(LevelAdvance::Minutes {
+            minutes_per_level: 10,
+        }));
+        let start = Utc::now();
+        tournament.update_level(start);
+        assert!(tournament.is_started());
+
+        tournament.update_level(start + Duration::minutes(9));
+        assert_eq!(tournament.level_index, 0);
+        // 1つのハンドの間に2レベル分が経過した。次のレベルの開始時刻は本来の区切りのまま
+        tournament.update_level(start + Duration::minutes(25));
+        assert_eq!(tournament.level_index, 2);
+        assert_eq!(
+            tournament.level_started_at,
+            Some(start + Duration::minutes(20))
+        );
+        tournament.update_level(start + Duration::minutes(60));
+        assert_eq!(tournament.level_index, 2);
+    }
+
+    #[test]
+    fn finishing_places_rank_busts_by_starting_stack_and_the_winner_first() {
+        let mut tournament =
+            TournamentState::new(config(LevelAdvance::Hands { hands_per_level: 5 }));
+        tournament.record_hand(vec![("e".to_string(), 300)], 4);
+        // 同じハンドで飛んだ場合は開始時のスタックが多い方が上位
+        tournament.record_hand(vec![("d".to_string(), 100), ("c".to_string(), 500)], 2);
+        tournament.record_hand(vec![("b".to_string(), 800)], 1);
+        tournament.finish("a".to_string());
+
+        let places: Vec<(&str, usize)> = tournament
+            .finishing_places
+            .iter()
+            .map(|p| (p.username.as_str(), p.place))
+            .collect();
+        assert_eq!(
+            places,
+            vec![("a", 1), ("b", 2), ("c", 3), ("d", 4), ("e", 5)]
+        );
+        assert!(tournament.is_finished);
+    }
+}
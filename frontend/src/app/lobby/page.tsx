@@ -17,6 +17,10 @@ export default function LobbyPage() {
     router.push(`/rooms/${roomId}`);
   };
 
+  const handleSpectateRoom = (roomId: string) => {
+    router.push(`/rooms/${roomId}?spectate=1`);
+  };
+
   useEffect(() => {
     if (isInitialized && !isLoggedIn) {
       router.push('/login');
@@ -66,8 +70,11 @@ export default function LobbyPage() {
             <ul style={{ listStyle: 'none', padding: 0 }}>
               {rooms.map((room) => (
                 <li key={room.id} style={{ border: '1px solid #555', padding: '1rem', marginBottom: '1rem', display: 'flex', justifyContent: 'space-between' }}>
-                  <span>{room.name}</span>
-                  <button onClick={() => handleJoinRoom(room.id)} style={{ padding: '0.5rem 1rem' }}>参加する</button>
+                  <span>{room.name}（観戦者: {room.spectator_count}人）</span>
+                  <span>
+                    <button onClick={() => handleJoinRoom(room.id)} style={{ padding: '0.5rem 1rem' }}>参加する</button>
+                    <button onClick={() => handleSpectateRoom(room.id)} style={{ padding: '0.5rem 1rem', marginLeft: '0.5rem' }}>観戦する</button>
+                  </span>
                 </li>
               ))}
             </ul>
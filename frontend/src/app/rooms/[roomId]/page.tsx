@@ -1,9 +1,9 @@
 'use client';
 
 import { useEffect, useState, useRef } from 'react';
-import { useParams } from 'next/navigation';
+import { useParams, useSearchParams } from 'next/navigation';
 import { useUserStore } from '@/store/userStore';
-import { Room, GameState, GameMessage } from '@/types';
+import { Room, GameState, GameMessage, LegalActions, PlayerStats } from '@/types';
 import { useRouter } from 'next/navigation';
 
 export default function RoomPage() {
@@ -11,6 +11,8 @@ export default function RoomPage() {
   const router = useRouter();
   const params = useParams();
   const roomId = params.roomId as string;
+  // ?spectate=1 なら着席せずに観戦する
+  const isSpectator = useSearchParams().get('spectate') === '1';
 
   const [room, setRoom] = useState<Room | null>(null);
   const [isLoading, setIsLoading] = useState<boolean>(true);
@@ -18,10 +20,19 @@ export default function RoomPage() {
 
   const [gameState, setGameState] = useState<GameState | null>(null);
   const [myHand, setMyHand] = useState<string[]>([]);
+  const [legalActions, setLegalActions] = useState<LegalActions | null>(null);
+  const [actionError, setActionError] = useState<string | null>(null);
+  const [playerStats, setPlayerStats] = useState<Record<string, PlayerStats>>({});
 
   const [betAmount, setBetAmount] = useState<number>(10);
-  const myPlayerState = gameState?.players.find(p => p.username === username);
-  const handleNextHand = () => handlePlayerAction({ action: 'NextHand' });
+  const handleNextHand = () => {
+    handlePlayerAction({ action: 'NextHand' });
+    sendClientSeed();
+  };
+  const handleTakeSeat = (seat: number) => {
+    handlePlayerAction({ action: 'TakeSeat', seat });
+    sendClientSeed();
+  };
 
   // WebSocketメッセージを管理するためのState
   const [chatMessages, setChatMessages] = useState<string[]>([]);
@@ -71,7 +82,8 @@ export default function RoomPage() {
 
     const connectWebSocket = () => {
       setConnectionStatus('接続中...');
-      const socket = new WebSocket(`ws://localhost:8000/api/ws/rooms/${roomId}`);
+      const query = isSpectator ? '?spectate=true' : '';
+      const socket = new WebSocket(`ws://localhost:8000/api/ws/rooms/${roomId}${query}`);
 
       socket.onopen = () => {
         setConnectionStatus('接続済み');
@@ -89,10 +101,30 @@ export default function RoomPage() {
               break;
             case 'GameStateUpdate':
               setGameState(message.payload);
+              setActionError(null);
+              // 自分の手番でなくなったら合法アクションを破棄する
+              if (message.payload.current_turn_username !== username) {
+                setLegalActions(null);
+              }
               break;
             case 'DealHand':
               setMyHand(message.payload.cards);
               break;
+            case 'LegalActions':
+              setLegalActions(message.payload);
+              break;
+            case 'Error':
+              setActionError(message.payload.message);
+              break;
+            case 'PlayerStats':
+              setPlayerStats((prev) => {
+                const next = { ...prev };
+                for (const stats of message.payload) {
+                  next[stats.username] = stats;
+                }
+                return next;
+              });
+              break;
           }
         } catch (e) {
           setChatMessages((prev) => [...prev, event.data]);
@@ -121,7 +153,7 @@ export default function RoomPage() {
       reconnectAttempt.current = 5;
       ws?.close();
     };
-  }, [isInitialized, isLoggedIn, roomId]);
+  }, [isInitialized, isLoggedIn, roomId, username, isSpectator]);
 
   // 「ゲーム開始」ボタンの処理
   const handleStartGame = () => {
@@ -148,6 +180,8 @@ export default function RoomPage() {
   };
 
   // プレイヤーのアクションを送信する関数
+  const mySeat = gameState?.players.find((p) => p.username === username);
+
   const handlePlayerAction = (action: any) => {
     if (ws?.readyState === WebSocket.OPEN) {
       const message = {
@@ -158,6 +192,18 @@ export default function RoomPage() {
     }
   };
 
+  // 次のハンドのシャッフルに混ぜるシードを送る（サーバーだけでデッキの順を決められないようにする）
+  const sendClientSeed = () => {
+    if (ws?.readyState === WebSocket.OPEN) {
+      const bytes = crypto.getRandomValues(new Uint8Array(16));
+      const message = {
+        type: 'ClientSeed',
+        payload: Array.from(bytes, (b) => b.toString(16).padStart(2, '0')).join(''),
+      };
+      ws.send(JSON.stringify(message));
+    }
+  };
+
 
 
   if (isLoading || !isInitialized) {
@@ -177,58 +223,123 @@ export default function RoomPage() {
       <div style={{ flex: 1, padding: '2rem', overflowY: 'auto' }}>
         <h1>{room?.name}</h1>
         <p>ルームID: {room?.id}</p>
+        <p>観戦者: {room?.spectator_count ?? 0}人{isSpectator && '（観戦中）'}</p>
         <hr style={{ margin: '1rem 0' }} />
 
-        {gameState?.status === 'Waiting' && (
+        {!isSpectator && gameState?.status === 'Waiting' && (
           <button onClick={handleStartGame} style={{ padding: '0.5rem 1rem', marginBottom: '1rem' }}>
             ゲーム開始
           </button>
         )}
 
-        {/* ショーダウン時の表示 */}
-        {gameState?.status === 'Showdown' && (
+        {/* 席の選択（空いている席に着席する） */}
+        {!isSpectator && gameState && !mySeat && (
+          <div style={{ marginBottom: '1rem' }}>
+            <strong>席を選んでください:</strong>{' '}
+            {Array.from({ length: gameState.config.max_seats }, (_, seat) => seat)
+              .filter((seat) => !gameState.players.some((p) => p.seat === seat))
+              .map((seat) => (
+                <button key={seat} onClick={() => handleTakeSeat(seat)} style={{ padding: '0.25rem 0.75rem', marginRight: '0.5rem' }}>
+                  {seat + 1}番席
+                </button>
+              ))}
+          </div>
+        )}
+
+        {/* 休憩・退席 */}
+        {mySeat && (
+          <div style={{ marginBottom: '1rem' }}>
+            {mySeat.is_sitting_out ? (
+              <button onClick={() => handlePlayerAction({ action: 'SitIn' })} style={{ padding: '0.5rem 1rem' }}>
+                休憩から戻る
+              </button>
+            ) : (
+              <button onClick={() => handlePlayerAction({ action: 'SitOut' })} style={{ padding: '0.5rem 1rem' }}>
+                休憩する
+              </button>
+            )}
+            <button onClick={() => handlePlayerAction({ action: 'LeaveTable' })} disabled={mySeat.is_leaving} style={{ padding: '0.5rem 1rem', marginLeft: '0.5rem' }}>
+              {mySeat.is_leaving ? 'ハンド終了後に退席します' : '席を立つ'}
+            </button>
+          </div>
+        )}
+
+        {/* ハンド終了時の表示 */}
+        {(gameState?.status === 'Showdown' || gameState?.status === 'HandComplete') && (
           <div style={{ marginTop: '1rem', padding: '1rem', border: '2px solid yellow', backgroundColor: '#330' }}>
-            <h2>ショーダウン</h2>
+            <h2>{gameState.status === 'Showdown' ? 'ショーダウン' : 'ハンド終了'}</h2>
             <p style={{ color: 'yellow', fontSize: '1.2rem' }}>{gameState.winner_message}</p>
+            {gameState.shuffle_proof?.server_seed && (
+              <p style={{ fontSize: '0.8rem', wordBreak: 'break-all' }}>
+                サーバーシード: {gameState.shuffle_proof.server_seed}（ハッシュ: {gameState.shuffle_proof.server_seed_hash}）
+              </p>
+            )}
             <button onClick={handleNextHand} style={{ padding: '0.5rem 1rem', marginTop: '1rem' }}>
               次のハンドへ
             </button>
+            {mySeat?.is_active && !mySeat.shows_hand && (
+              <button onClick={() => handlePlayerAction({ action: 'ShowCards' })} style={{ padding: '0.5rem 1rem', marginTop: '1rem', marginLeft: '0.5rem' }}>
+                手札を見せる
+              </button>
+            )}
           </div>
         )}
 
-        {/* アクションボタンエリア */}
-        {gameState && gameState.current_turn_username === username && myPlayerState && (
+        {actionError && (
+          <p style={{ color: 'red', marginTop: '1rem' }}>{actionError}</p>
+        )}
+
+        {/* アクションボタンエリア（サーバーから届いた合法アクションのみ表示） */}
+        {gameState && gameState.current_turn_username === username && legalActions && (
           <div style={{ marginTop: '1rem', padding: '1rem', border: '2px solid lightgreen' }}>
             <h2>Your Turn!</h2>
             <div style={{ display: 'flex', gap: '1rem', marginTop: '0.5rem' }}>
-              <button onClick={() => handlePlayerAction({ action: 'Fold' })} style={{ padding: '0.5rem 1rem' }}>
-                フォールド
-              </button>
-
-              {/* 状況に応じてチェック/コールを出し分け */}
-              {myPlayerState.current_bet < gameState.current_bet ? (
-                <button onClick={() => handlePlayerAction({ action: 'Call' })} style={{ padding: '0.5rem 1rem', backgroundColor: '#2a4' }}>
-                  コール ({gameState.current_bet})
+              {legalActions.can_fold && (
+                <button onClick={() => handlePlayerAction({ action: 'Fold' })} style={{ padding: '0.5rem 1rem' }}>
+                  フォールド
                 </button>
-              ) : (
-                <button onClick={() => handlePlayerAction({ action: 'Call' })} style={{ padding: '0.5rem 1rem', backgroundColor: '#44b' }}>
+              )}
+
+              {legalActions.can_check && (
+                <button onClick={() => handlePlayerAction({ action: 'Check' })} style={{ padding: '0.5rem 1rem', backgroundColor: '#44b' }}>
                   チェック
                 </button>
               )}
 
+              {legalActions.call_amount !== null && (
+                <button onClick={() => handlePlayerAction({ action: 'Call' })} style={{ padding: '0.5rem 1rem', backgroundColor: '#2a4' }}>
+                  コール ({legalActions.call_amount})
+                </button>
+              )}
+
               {/* ベット/レイズボタン */}
-              <div>
-                <input
-                  type="number"
-                  value={betAmount}
-                  onChange={(e) => setBetAmount(Number(e.target.value))}
-                  style={{ width: '80px', color: 'black', padding: '0.5rem' }}
-                  min={gameState.current_bet * 2} // 簡単なバリデーション
-                />
-                <button onClick={() => handlePlayerAction({ action: 'Bet', amount: betAmount })} style={{ padding: '0.5rem 1rem', backgroundColor: '#c33' }}>
-                  {gameState.current_bet > 0 ? 'レイズ' : 'ベット'}
+              {(legalActions.bet || legalActions.raise) && (
+                <div>
+                  <input
+                    type="number"
+                    value={betAmount}
+                    onChange={(e) => setBetAmount(Number(e.target.value))}
+                    style={{ width: '80px', color: 'black', padding: '0.5rem' }}
+                    min={(legalActions.bet ?? legalActions.raise)?.min}
+                    max={(legalActions.bet ?? legalActions.raise)?.max}
+                  />
+                  {legalActions.raise ? (
+                    <button onClick={() => handlePlayerAction({ action: 'Raise', to: betAmount })} style={{ padding: '0.5rem 1rem', backgroundColor: '#c33' }}>
+                      レイズ ({legalActions.raise.min}〜{legalActions.raise.max})
+                    </button>
+                  ) : (
+                    <button onClick={() => handlePlayerAction({ action: 'Bet', amount: betAmount })} style={{ padding: '0.5rem 1rem', backgroundColor: '#c33' }}>
+                      ベット ({legalActions.bet?.min}〜{legalActions.bet?.max})
+                    </button>
+                  )}
+                </div>
+              )}
+
+              {legalActions.all_in !== null && (
+                <button onClick={() => handlePlayerAction({ action: 'AllIn' })} style={{ padding: '0.5rem 1rem', backgroundColor: '#a3a' }}>
+                  オールイン ({legalActions.all_in})
                 </button>
-              </div>
+              )}
             </div>
           </div>
         )}
@@ -243,7 +354,7 @@ export default function RoomPage() {
           <hr />
           <h3>Players:</h3>
           <ul>
-            {gameState?.players.map((p, index) => {
+            {gameState?.players.map((p) => {
               let color = '#DDD';
               if (p.username === gameState.current_turn_username) {
                 color = 'lightgreen';
@@ -251,19 +362,32 @@ export default function RoomPage() {
                 color = 'grey';
               }
 
-              const isDealer = index === gameState.dealer_index;
-              const isSB = index === (gameState.dealer_index + 1) % gameState.players.length;
-              const isBB = index === (gameState.dealer_index + 2) % gameState.players.length;
+              const isDealer = p.seat === gameState.button_seat;
+              const isSB = p.seat === gameState.small_blind_seat;
+              const isBB = p.seat === gameState.big_blind_seat;
 
               return (
                 <li key={p.username} style={{ color, fontWeight: isDealer ? 'bold' : 'normal' }}>
                   {isDealer && 'D '}{isSB && 'SB '}{isBB && 'BB '}
-                  {p.username} (Stack: {p.stack}) [Bet: {p.current_bet}]
-                  {/* ショーダウン時に手札を表示 */}
-                  {gameState.status === 'Showdown' && p.hand.length > 0 && ` [Hand: ${p.hand.join(', ')}]`}
+                  [{p.seat + 1}番席] {p.username} (Stack: {p.stack}) [Bet: {p.current_bet}]
+                  {/* ショーダウンやオールイン時に公開された手札を表示 */}
+                  {p.hand.length > 0 && ` [Hand: ${p.hand.join(', ')}]`}
                   {p.username === username && ' (You)'}
+                  {p.disconnected_at && ' (離席中)'}
+                  {p.is_sitting_out && ' (休憩中)'}
+                  {p.is_leaving && ' (退席予定)'}
                   {!p.is_active && gameState.status !== 'Waiting' && ' (Folded)'}
+                  {p.is_active && !p.shows_hand && gameState.status === 'Showdown' && ' (Muck)'}
                   {p.username === gameState.current_turn_username && ' (Turn)'}
+                  {/* HUD: VPIP / PFR / AF（ハンド数） */}
+                  {playerStats[p.username] && playerStats[p.username].hands > 0 && (
+                    <span style={{ color: '#888', fontSize: '0.8em' }}>
+                      {' '}[{playerStats[p.username].vpip.toFixed(0)}/
+                      {playerStats[p.username].pfr.toFixed(0)}/
+                      {playerStats[p.username].aggression_factor?.toFixed(1) ?? '-'} (
+                      {playerStats[p.username].hands})]
+                    </span>
+                  )}
                 </li>
               );
             })}
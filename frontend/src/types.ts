@@ -4,6 +4,56 @@ export interface Room {
   status: 'waiting' | 'playing' | 'finished';
   created_by: string;
   created_at: string;
+  small_blind: number;
+  big_blind: number;
+  ante: number;
+  starting_stack: number;
+  max_seats: number;
+  min_players: number;
+  action_timeout_secs: number;
+  time_bank_secs: number;
+  spectator_delay_secs: number;
+  tournament: TournamentConfig | null;
+  spectator_count: number;
+}
+
+// トーナメントのブラインドレベル
+export interface BlindLevel {
+  small_blind: number;
+  big_blind: number;
+  ante: number;
+}
+
+// トーナメントの設定
+export interface TournamentConfig {
+  levels: BlindLevel[];
+  advance:
+    | { type: 'Hands'; hands_per_level: number }
+    | { type: 'Minutes'; minutes_per_level: number };
+}
+
+// トーナメントの進行状況
+export interface TournamentState {
+  config: TournamentConfig;
+  level_index: number;
+  hands_played_in_level: number;
+  level_started_at: string | null;
+  finishing_places: { username: string; place: number }[];
+  is_finished: boolean;
+}
+
+// テーブルのルール設定
+export interface TableConfig {
+  small_blind: number;
+  big_blind: number;
+  ante: number;
+  starting_stack: number;
+  max_seats: number;
+  min_players: number;
+  action_timeout_secs: number;
+  time_bank_secs: number;
+  spectator_delay_secs: number;
+  tournament: TournamentConfig | null;
 }
 
 // プレイヤーの状態
@@ -13,22 +63,189 @@ export interface Player {
   hand: string[];
   is_active: boolean;
   current_bet: number;
+  total_bet: number;
+  is_all_in: boolean;
+  has_acted: boolean;
+  time_bank_secs: number;
+  disconnected_at: string | null;
+  seat: number;
+  is_sitting_out: boolean;
+  is_leaving: boolean;
+  missed_small_blind: boolean;
+  missed_big_blind: boolean;
+  shows_hand: boolean; // 手札をテーブルに公開している
 }
 
+// 手番プレイヤーの持ち時間
+export interface TurnTimer {
+  username: string;
+  deadline: string;
+  using_time_bank: boolean;
+}
+
+// ポット（メインポット・サイドポット）
+export interface Pot {
+  amount: number;
+  eligible_players: string[];
+}
+
+// ゲームの進行フェーズ
+export type GamePhase =
+  | 'Waiting'
+  | 'Pre-flop'
+  | 'Flop'
+  | 'Turn'
+  | 'River'
+  | 'Showdown'
+  | 'HandComplete'
+  | 'Finished';
+
 // ゲーム全体の状態
 export interface GameState {
   players: Player[];
   community_cards: string[];
   pot: number;
+  pots: Pot[];
   current_turn_username: string | null;
-  status: string;
+  status: GamePhase;
   current_bet: number;
-  dealer_index: number;
+  min_raise: number;
+  button_seat: number | null; // デッドボタンなら空席のこともある
+  small_blind_seat: number | null;
+  big_blind_seat: number | null;
+  last_aggressor: string | null; // このストリートで最後にベット・レイズしたプレイヤー
   winner_message: string | null;
+  config: TableConfig;
+  tournament: TournamentState | null;
+  turn_timer: TurnTimer | null;
+  runout_at: string | null; // 全員オールインのとき、次のストリートが配られる時刻
+  seed_commitment: string; // 次のハンドで使うサーバーシードのハッシュ
+  client_seeds: Record<string, string>; // 次のハンドから混ぜるプレイヤーのシード
+  shuffle_proof: ShuffleProof | null; // 現在（直前）のハンドのシャッフルの証明
+}
+
+// シャッフルの証明。サーバーシードはハンドが終わってから公開される
+export interface ShuffleProof {
+  server_seed_hash: string;
+  server_seed: string | null;
+  client_seeds: { username: string; seed: string }[];
+}
+
+// ベット・レイズできる額の範囲
+export interface BetRange {
+  min: number;
+  max: number;
+}
+
+// 手番のプレイヤーが取れるアクションの一覧
+export interface LegalActions {
+  username: string;
+  can_fold: boolean;
+  can_check: boolean;
+  call_amount: number | null;
+  bet: BetRange | null;
+  raise: BetRange | null;
+  all_in: number | null;
+}
+
+// サーバーから返されるエラー
+export interface GameErrorPayload {
+  error: { code: string; min?: number; max?: number };
+  message: string;
+}
+
+// プレイヤーのHUD統計（割合は0〜100のパーセント）
+export interface PlayerStats {
+  username: string;
+  hands: number;
+  vpip: number;
+  pfr: number;
+  three_bet: number;
+  aggression_factor: number | null;
+  wtsd: number;
+  wsd: number;
+  bb_per_100: number;
 }
 
 // WebSocketで送受信するメッセージの型
 export type GameMessage =
   | { type: 'ChatMessage'; payload: string }
+  | { type: 'ClientSeed'; payload: string }
   | { type: 'GameStateUpdate'; payload: GameState }
-  | { type: 'DealHand'; payload: { cards: string[] } };
\ No newline at end of file
+  | { type: 'DealHand'; payload: { cards: string[] } }
+  | { type: 'LegalActions'; payload: LegalActions }
+  | { type: 'Error'; payload: GameErrorPayload }
+  | { type: 'PlayerStats'; payload: PlayerStats[] };
+// 戦績の1件（1ハンド分）
+export interface HistoryEntry {
+  game_result_id: string;
+  room_id: string;
+  room_name: string;
+  pot: number;
+  winning_hand_rank: string | null;
+  finished_at: string;
+  net_chips: number;
+  hand_rank: string | null;
+  is_winner: boolean;
+  participants: string[];
+}
+
+// 戦績取得APIのレスポンス
+export interface HistoryPage {
+  results: HistoryEntry[];
+  total: number;
+  limit: number;
+  offset: number;
+}
+
+// ハンド記録の出来事（リプレイ用）
+export type HandEvent =
+  | { type: 'PostAnte'; username: string; amount: number }
+  | { type: 'PostSmallBlind'; username: string; amount: number }
+  | { type: 'PostBigBlind'; username: string; amount: number }
+  | { type: 'PostMissedBlinds'; username: string; live: number; dead: number }
+  | { type: 'DealHoleCards'; username: string; cards: string[] }
+  | ({
+      type: 'PlayerAction';
+      street: GamePhase;
+      username: string;
+      chips: number;
+      is_all_in: boolean;
+    } & PlayerActionPayload)
+  | { type: 'DealBoard'; street: GamePhase; cards: string[] }
+  | { type: 'Showdown'; username: string; cards: string[]; hand_rank: string }
+  | { type: 'MuckCards'; username: string }
+  | { type: 'ReturnUncalledBet'; username: string; amount: number }
+  | { type: 'CollectPot'; username: string; amount: number; pot_index: number };
+
+// ハンド中のプレイヤーのアクション
+export type PlayerActionPayload =
+  | { action: 'Fold' }
+  | { action: 'Check' }
+  | { action: 'Call' }
+  | { action: 'Bet'; amount: number }
+  | { action: 'Raise'; to: number }
+  | { action: 'AllIn' };
+
+// 1ハンド分の記録
+export interface HandHistory {
+  started_at: string;
+  dealer: string;
+  button_seat?: number | null;
+  small_blind: number;
+  big_blind: number;
+  ante: number;
+  seed?: string | null; // デッキのシャッフルに使ったシード（16進数）
+  shuffle?: ShuffleProof | null;
+  seats: { seat: number; username: string; stack: number }[];
+  board: string[];
+  events: HandEvent[];
+}
+
+// ハンド記録取得APIのレスポンス
+export interface HandReplay {
+  id: string;
+  room_id: string;
+  finished_at: string;
+  history: HandHistory;
+}